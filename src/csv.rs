@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{self, Read};
+
+use gildedrose::{GildedRose, Item};
+
+const CSV_HEADER: &str = "name,sell_in,quality";
+
+
+/// Why a CSV inventory could not be loaded. Line numbers start at 1 with the header row.
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    MalformedRow { line: usize },
+    InvalidNumber { line: usize, field: &'static str, value: String },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(error) => write!(f, "could not read CSV: {}", error),
+            CsvError::MalformedRow { line } => write!(f, "line {}: expected `{}`", line, CSV_HEADER),
+            CsvError::InvalidNumber { line, field, value } => {
+                write!(f, "line {}: {} `{}` is not a whole number", line, field, value)
+            },
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> CsvError {
+        CsvError::Io(error)
+    }
+}
+
+
+impl GildedRose {
+    /// Loads a shop from CSV with a `name,sell_in,quality` header row. Names may be quoted to
+    /// contain commas, with `""` standing for a quote inside a quoted name. Blank lines are
+    /// skipped.
+    pub fn from_csv(mut reader: impl Read) -> Result<GildedRose, CsvError> {
+        let mut csv = String::new();
+        reader.read_to_string(&mut csv)?;
+
+        let mut lines = csv.lines().enumerate().map(|(index, line)| (index + 1, line));
+        match lines.next() {
+            Some((_, header)) if header.trim() == CSV_HEADER => {},
+            _ => return Err(CsvError::MalformedRow { line: 1 }),
+        }
+
+        let mut items = Vec::new();
+        for (line, row) in lines {
+            if row.trim().is_empty() {
+                continue;
+            }
+            items.push(parse_row(line, row)?);
+        }
+        Ok(GildedRose::new(items))
+    }
+
+    /// Writes the items as CSV in the format read by `from_csv`, quoting names that need it.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        for item in &self.items {
+            csv.push('\n');
+            csv.push_str(&quote_field(&item.name));
+            csv.push_str(&format!(",{},{}", item.sell_in, item.quality));
+        }
+        csv.push('\n');
+        csv
+    }
+}
+
+fn quote_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn parse_row(line: usize, row: &str) -> Result<Item, CsvError> {
+    let fields = split_fields(row).ok_or(CsvError::MalformedRow { line })?;
+    if fields.len() != 3 {
+        return Err(CsvError::MalformedRow { line });
+    }
+
+    let sell_in = parse_number(line, "sell_in", &fields[1])?;
+    let quality = parse_number(line, "quality", &fields[2])?;
+    Ok(Item::new(fields[0].as_str(), sell_in, quality))
+}
+
+fn parse_number(line: usize, field: &'static str, value: &str) -> Result<i32, CsvError> {
+    value.trim().parse().map_err(|_| CsvError::InvalidNumber { line, field, value: value.to_string() })
+}
+
+/// Splits a row on commas outside of quotes, returning `None` for an unterminated quote.
+fn split_fields(row: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use gildedrose::{GildedRose, Item, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM, COMMON_ITEM};
+    use super::CsvError;
+
+    #[test]
+    fn test_well_formed_csv() {
+        // GIVEN a CSV inventory
+        let csv = "name,sell_in,quality\n\
+                   Elixir of the Mongoose,5,7\n\
+                   Aged Brie,2,0\n";
+
+        // WHEN loading it
+        let rose = GildedRose::from_csv(csv.as_bytes()).unwrap();
+
+        // THEN every row should become an item
+        assert_eq!(vec![
+            Item::new(COMMON_ITEM, 5, 7),
+            Item::new(AGED_BRIE_ITEM, 2, 0),
+        ], rose.items);
+    }
+
+    #[test]
+    fn test_quoted_name() {
+        // GIVEN a CSV inventory with quoted names containing commas and quotes
+        let csv = "name,sell_in,quality\n\
+                   \"Backstage passes to a TAFKAL80ETC concert\",15,20\n\
+                   \"Sulfuras, Hand of Ragnaros\",0,80\n\
+                   \"The \"\"Best\"\" Cheese\",3,10\n";
+
+        // WHEN loading it
+        let rose = GildedRose::from_csv(csv.as_bytes()).unwrap();
+
+        // THEN the names should keep their commas and quotes
+        assert_eq!(BACKSTAGE_PASSES_ITEM, rose.items[0].name);
+        assert_eq!(Item::new("Sulfuras, Hand of Ragnaros", 0, 80), rose.items[1]);
+        assert_eq!("The \"Best\" Cheese", rose.items[2].name);
+    }
+
+    #[test]
+    fn test_non_numeric_quality() {
+        // GIVEN a CSV inventory with a non-numeric quality on the third line
+        let csv = "name,sell_in,quality\n\
+                   Aged Brie,2,0\n\
+                   Elixir of the Mongoose,5,lots\n";
+
+        // WHEN loading it
+        let result = GildedRose::from_csv(csv.as_bytes());
+
+        // THEN the offending line and field should be reported
+        match result {
+            Err(CsvError::InvalidNumber { line, field, value }) => {
+                assert_eq!(3, line);
+                assert_eq!("quality", field);
+                assert_eq!("lots", value);
+            },
+            _ => panic!("expected an invalid number error"),
+        }
+    }
+
+    #[test]
+    fn test_negative_sell_in() {
+        // GIVEN a CSV inventory with an expired item
+        let csv = "name,sell_in,quality\n\
+                   Elixir of the Mongoose, -5, 0\n";
+
+        // WHEN loading it
+        let rose = GildedRose::from_csv(csv.as_bytes()).unwrap();
+
+        // THEN the minus sign should be kept
+        assert_eq!(-5, rose.items[0].sell_in);
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        // GIVEN a shop with names that need quoting
+        let rose = GildedRose::new(vec![
+            Item::new(COMMON_ITEM, 5, 7),
+            Item::new("Sulfuras, Hand of Ragnaros", 0, 80),
+            Item::new("The \"Best\" Cheese", 3, 10),
+        ]);
+
+        // WHEN writing it as CSV and loading it back
+        let csv = rose.to_csv();
+        let loaded = GildedRose::from_csv(csv.as_bytes()).unwrap();
+
+        // THEN the items should be unchanged
+        assert!(csv.starts_with("name,sell_in,quality\n"));
+        assert_eq!(rose.items, loaded.items);
+    }
+
+    #[test]
+    fn test_malformed_rows() {
+        let missing_header = "Aged Brie,2,0\n";
+        let missing_field = "name,sell_in,quality\nAged Brie,2\n";
+        let unterminated_quote = "name,sell_in,quality\n\"Aged Brie,2,0\n";
+
+        for &(csv, expected_line) in &[(missing_header, 1), (missing_field, 2), (unterminated_quote, 2)] {
+            match GildedRose::from_csv(csv.as_bytes()) {
+                Err(CsvError::MalformedRow { line }) => assert_eq!(expected_line, line),
+                _ => panic!("expected a malformed row error for {:?}", csv),
+            }
+        }
+    }
+}
@@ -1,20 +1,56 @@
-use std::cmp;
-use std::fmt::{self, Display};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::{self, Vec};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt::{self, Display};
+use core::iter::{self, FromIterator};
+use core::mem;
+use core::ops::{Deref, Index};
+use core::slice;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::io::{self, BufRead};
 
-const AGED_BRIE_ITEM: &str = "Aged Brie";
-const BACKSTAGE_PASSES_ITEM: &str = "Backstage passes to a TAFKAL80ETC concert";
-const COMMON_ITEM: &str = "Elixir of the Mongoose";
-const CONJURED_ITEM: &str = "Conjured Mana Cake";
-const LEGENDARY_ITEM: &str = "Sulfuras, Hand of Ragnaros";
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub const AGED_BRIE_ITEM: &str = "Aged Brie";
+pub const BACKSTAGE_PASSES_ITEM: &str = "Backstage passes to a TAFKAL80ETC concert";
+pub const COMMON_ITEM: &str = "Elixir of the Mongoose";
+pub const CONJURED_ITEM: &str = "Conjured Mana Cake";
+pub const LEGENDARY_ITEM: &str = "Sulfuras, Hand of Ragnaros";
+
+const CONJURED_PREFIX: &str = "Conjured ";
 
 const MAXIMUM_ALLOWED_QUALITY: i32 = 50;
 const MINIMUM_ALLOWED_QUALITY: i32 = 0;
+const LEGENDARY_QUALITY: i32 = 80;
 
+/// How many days ahead `Item::days_to_quality` looks.
+pub const DAYS_TO_QUALITY_HORIZON: u32 = 1000;
 
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Item {
     pub name: String,
     pub sell_in: i32,
     pub quality: i32,
+    /// The quality a common item loses per day before its sell by date, replacing the usual 1.
+    /// It still loses twice as much after the sell by date. Other items ignore it.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub degrade_rate: Option<i32>,
+    /// Frozen items keep their `sell_in` and quality on updates until they are unfrozen, like
+    /// legendary items always do.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "core::ops::Not::not"))]
+    pub frozen: bool,
 }
 
 impl Item {
@@ -23,326 +59,6222 @@ impl Item {
             name: name.into(),
             sell_in,
             quality,
+            degrade_rate: None,
+            frozen: false,
         }
     }
-}
 
-impl Display for Item {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}, {}, {}", self.name, self.sell_in, self.quality)
+    /// Creates an item with typical starting values for the kind of item `name` is, such as
+    /// quality 80 for legendary items and 0 for aged brie.
+    pub fn fresh(name: impl Into<String>) -> Item {
+        let name = name.into();
+        let (sell_in, quality) = ItemKind::from_name(&resolve_name(&name)).fresh_state();
+        Item::new(name, sell_in, quality)
     }
-}
 
+    pub fn builder() -> ItemBuilder {
+        ItemBuilder::default()
+    }
 
-pub struct GildedRose {
-    pub items: Vec<Item>,
-}
-
-impl GildedRose {
-    pub fn new(items: Vec<Item>) -> GildedRose {
-        GildedRose { items }
+    /// Renames the item, returning it for chaining further changes.
+    pub fn with_name(&mut self, name: impl Into<String>) -> &mut Item {
+        self.name = name.into();
+        self
     }
 
-    pub fn update_quality(&mut self) {
-        for item in &mut self.items {
+    /// Sets `sell_in`, returning the item for chaining further changes.
+    pub fn with_sell_in(&mut self, sell_in: i32) -> &mut Item {
+        self.sell_in = sell_in;
+        self
+    }
 
-            if item.name == LEGENDARY_ITEM {
-                continue;
-            }
+    /// Sets the quality, returning the item for chaining further changes.
+    pub fn with_quality(&mut self, quality: i32) -> &mut Item {
+        self.quality = quality;
+        self
+    }
 
-            item.quality = match item.name.as_str() {
-                AGED_BRIE_ITEM => Self::quality_increasing_update_strategy(item),
-                BACKSTAGE_PASSES_ITEM => Self::backstage_passes_update_strategy(item),
-                CONJURED_ITEM => Self::faster_degrading_update_strategy(item),
-                _ => Self::default_update_strategy(item)
-            };
+    /// Writes the item like `Display` does, with `separator` between the fields instead of
+    /// `", "`, such as `"Aged Brie;2;0"` for semicolon-delimited output.
+    pub fn to_string_with_separator(&self, separator: &str) -> String {
+        format!("{}{}{}{}{}", self.name, separator, self.sell_in, separator, self.quality)
+    }
 
-            item.sell_in -= 1;
-        }
+    /// Whether the sell by date has passed. A `sell_in` of 0 is the last day of sale and does not
+    /// count as expired yet.
+    pub fn is_expired(&self) -> bool {
+        self.sell_in < 0
     }
 
-    fn quality_increasing_update_strategy(item: &Item) -> i32 {
-        let quality_adjustment =
-            if item.sell_in <= 0 {
-                2
-            } else {
-                1
-            };
-        return Self::get_updated_quality_within_bounds(item, quality_adjustment);
+    /// The number of days left to sell the item, which is its `sell_in`.
+    pub fn days_until_expiry(&self) -> i32 {
+        self.sell_in
     }
 
-    fn backstage_passes_update_strategy(item: &Item) -> i32 {
-        if item.sell_in <= 0 {
-             return 0
+    /// Which way the built-in behavior for this item's name moves its quality on the next
+    /// update, ignoring the quality bounds. Strategies registered on a shop are not considered.
+    pub fn value_trend(&self) -> Trend {
+        match daily_quality_delta(self).cmp(&0) {
+            Ordering::Greater => Trend::Increasing,
+            Ordering::Less => Trend::Decreasing,
+            Ordering::Equal => Trend::Stable,
         }
+    }
 
-        let quality_adjustment =
-            if item.sell_in <= 5 {
-                3
-            } else if item.sell_in <= 10 {
-                2
-            } else {
-                1
-            };
-        return Self::get_updated_quality_within_bounds(item, quality_adjustment);
+    /// The number of days until the item's quality is at least `target` under `update_item` with
+    /// `min` and `max`: 0 when it already is, and `None` when it does not get there within
+    /// `DAYS_TO_QUALITY_HORIZON` days, like items that lose quality.
+    pub fn days_to_quality(&self, target: i32, min: i32, max: i32) -> Option<u32> {
+        if self.quality >= target {
+            return Some(0);
+        }
+        self.trajectory(min, max)
+            .take(DAYS_TO_QUALITY_HORIZON as usize)
+            .position(|item| item.quality >= target)
+            .map(|days| days as u32 + 1)
     }
 
-    fn faster_degrading_update_strategy(item: &Item) -> i32 {
-        let quality_adjustment =
-            if item.sell_in <= 0 {
-                -4
-            } else {
-                -2
-            };
-        return Self::get_updated_quality_within_bounds(item, quality_adjustment);
+    /// Lazily yields the item's state after each coming day, as `update_item` with `min` and
+    /// `max` would leave it, without changing the item itself. The iterator never ends, so take
+    /// as many days as needed.
+    ///
+    /// Panics on the first `next` if `min` is greater than `max`.
+    pub fn trajectory(&self, min: i32, max: i32) -> impl Iterator<Item = Item> {
+        let mut item = self.clone();
+        iter::repeat_with(move || {
+            update_item(&mut item, min, max);
+            item.clone()
+        })
     }
+}
 
-    fn default_update_strategy(item: &Item) -> i32 {
-        let quality_adjustment =
-            if item.sell_in <= 0 {
-                -2
-            } else {
-                -1
-            };
-        return Self::get_updated_quality_within_bounds(item, quality_adjustment);
+/// Orders the most valuable items first: by quality descending, then by sooner `sell_in`, then
+/// by name, by degrade rate and finally with unfrozen items first.
+impl Ord for Item {
+    fn cmp(&self, other: &Item) -> Ordering {
+        other.quality.cmp(&self.quality)
+            .then(self.sell_in.cmp(&other.sell_in))
+            .then_with(|| self.name.cmp(&other.name))
+            .then(self.degrade_rate.cmp(&other.degrade_rate))
+            .then(self.frozen.cmp(&other.frozen))
     }
+}
 
-    fn get_updated_quality_within_bounds(item: &Item, adjust_by: i32) -> i32 {
-        let new_quality = item.quality + adjust_by;
-        cmp::max(
-            cmp::min(
-                new_quality,
-                MAXIMUM_ALLOWED_QUALITY
-            ),
-            MINIMUM_ALLOWED_QUALITY
-        )
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Item) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{GildedRose, Item, COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+/// The quality change the built-in behavior for `item`'s name makes on the next update, before
+/// it is kept within any bounds. Backstage passes after the concert lose all their quality.
+/// Strategies registered on a shop are not considered.
+pub fn daily_quality_delta(item: &Item) -> i32 {
+    let (_, quality) = builtin_state(item, i32::MIN, i32::MAX);
+    quality.saturating_sub(item.quality)
+}
 
-    mod regression_test_suite {
-        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
-        use super::{GildedRose, Item};
+/// The quality the default backstage pass behavior would give `item` on the next update, both
+/// before and after keeping it within the default bounds of 0 and 50, as `(uncapped, capped)`.
+/// Tells a pass that gained its full amount apart from one that maxed out.
+pub fn backstage_target(item: &Item) -> (i32, i32) {
+    let strategy = &DEFAULT_BACKSTAGE_PASS_STRATEGY;
+    let (_, uncapped) = strategy.next_state(item, i32::MIN, i32::MAX);
+    let (_, capped) = strategy.next_state(item, MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY);
+    (uncapped, capped)
+}
 
-        #[test]
-        fn test_common_items() {
-            // GIVEN common items
-            let items = vec![
-                Item::new(COMMON_ITEM, 10, 20),
-                Item::new(COMMON_ITEM, 5, 7),
-            ];
-            let mut rose = GildedRose::new(items);
+/// Runs one day's update on a single item, keeping its quality within `min` and `max`, the way
+/// a shop without registered strategies or other settings would. Useful for updating items
+/// from a stream without collecting them into a shop first.
+///
+/// Panics if `min` is greater than `max`.
+pub fn update_item(item: &mut Item, min: i32, max: i32) {
+    let (sell_in, quality) = builtin_state(item, min, max);
+    item.sell_in = sell_in;
+    item.quality = quality;
+}
 
-            // WHEN updating quality
-            rose.update_quality();
+/// Runs one day's update on every item in `items`, keeping their quality within `min` and
+/// `max`, the way `update_quality` would in a shop without registered strategies or other
+/// settings. For items owned elsewhere that should not be moved into a shop.
+///
+/// Panics if `min` is greater than `max`.
+pub fn update_slice(items: &mut [Item], min: i32, max: i32) {
+    assert!(min <= max, "minimum quality {} is greater than maximum quality {}", min, max);
+    for item in items {
+        update_item(item, min, max);
+    }
+}
 
-            // THEN name should stay the same and quality should decrease
-            assert_eq!(COMMON_ITEM, rose.items[0].name);
-            assert_eq!(9, rose.items[0].sell_in);
-            assert_eq!(19, rose.items[0].quality);
+/// The next `(sell_in, quality)` of `item` in a shop with the given bounds and nothing else
+/// configured, worked out without one.
+fn builtin_state(item: &Item, min: i32, max: i32) -> (i32, i32) {
+    let name = resolve_name(&item.name);
+    let strategy = ResolvedStrategy::builtin(ItemKind::from_name(&name), ItemKind::from_name(base_name(&name).0));
+    frozen_or_next_state(item, &strategy, min, max, 0)
+}
 
-            assert_eq!(6, rose.items[1].quality);
+/// The next `(sell_in, quality)` of `item` following `strategy`, or its current ones when it is
+/// frozen. The step of an update that shops and `update_item` share.
+fn frozen_or_next_state(
+    item: &Item, strategy: &dyn UpdateStrategy, min: i32, max: i32, expiry_threshold: i32,
+) -> (i32, i32) {
+    if item.frozen {
+        return (item.sell_in, item.quality);
+    }
+    strategy.next_state_with_expiry(item, min, max, expiry_threshold)
+}
 
-            // ...and WHEN update quality 4 more times
-            for _ in 1..=4 {
-                rose.update_quality();
-            }
+/// The direction an item's quality is heading, as returned by `Item::value_trend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    Increasing,
+    Decreasing,
+    Stable,
+}
 
-            // THEN quality should continue to degrade
-            assert_eq!(5, rose.items[0].sell_in);
-            assert_eq!(15, rose.items[0].quality);
+impl Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}, {}", self.name, self.sell_in, self.quality)
+    }
+}
 
-            assert_eq!(0, rose.items[1].sell_in);
-            assert_eq!(2, rose.items[1].quality);
 
-            // ...and WHEN updating quality again so sell in becomes negative
-            rose.update_quality();
+/// Parses the `name, sell_in, quality` format `Item` is displayed in. The last two fields are
+/// the numbers, so the name may contain commas itself.
+impl<'a> TryFrom<&'a str> for Item {
+    type Error = ItemParseError;
 
-            // THEN sell in should become negative and quality should degrade twice as fast
-            assert_eq!(-1, rose.items[1].sell_in);
-            assert_eq!(0, rose.items[1].quality);
+    fn try_from(line: &'a str) -> Result<Item, ItemParseError> {
+        let mut fields = line.rsplitn(3, ", ");
+        let (quality, sell_in, name) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(quality), Some(sell_in), Some(name)) => (quality, sell_in, name),
+            _ => return Err(ItemParseError::MissingFields),
+        };
 
-            // ...and WHEN updating quality when quality is 0
-            rose.update_quality();
+        let parse = |field: &'static str, value: &str| value.trim().parse().map_err(|_| {
+            ItemParseError::InvalidNumber { field, value: value.to_string() }
+        });
+        Ok(Item::new(name, parse("sell_in", sell_in)?, parse("quality", quality)?))
+    }
+}
 
-            // THEN quality should not become negative
-            assert_eq!(-2, rose.items[1].sell_in);
-            assert_eq!(0, rose.items[1].quality);
+/// Why a line could not be parsed as an `Item`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemParseError {
+    MissingFields,
+    InvalidNumber { field: &'static str, value: String },
+}
+
+impl Display for ItemParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemParseError::MissingFields => write!(f, "expected `name, sell_in, quality`"),
+            ItemParseError::InvalidNumber { field, value } => write!(f, "{} `{}` is not a whole number", field, value),
         }
+    }
+}
 
-        #[test]
-        fn test_legendary_items() {
-            // GIVEN legendary items of different sell in
-            let items = vec![
-                Item::new(LEGENDARY_ITEM, 1, 80),
-                Item::new(LEGENDARY_ITEM, 0, 80),
-                Item::new(LEGENDARY_ITEM, -1, 80)
-            ];
-            let mut rose = GildedRose::new(items);
+impl Error for ItemParseError {}
 
-            // WHEN updating quality
-            rose.update_quality();
 
-            // THEN nothing should change
-            assert_eq!(LEGENDARY_ITEM, rose.items[0].name);
-            assert_eq!(1, rose.items[0].sell_in);
-            assert_eq!(80, rose.items[0].quality);
+/// Builds an `Item`, rejecting values the shop would otherwise silently correct on the first
+/// update.
+#[derive(Clone, Debug, Default)]
+pub struct ItemBuilder {
+    name: Option<String>,
+    sell_in: i32,
+    quality: i32,
+}
 
-            assert_eq!(0, rose.items[1].sell_in);
-            assert_eq!(80, rose.items[1].quality);
+impl ItemBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> ItemBuilder {
+        self.name = Some(name.into());
+        self
+    }
 
-            assert_eq!(-1, rose.items[2].sell_in);
-            assert_eq!(80, rose.items[2].quality);
+    pub fn sell_in(mut self, sell_in: i32) -> ItemBuilder {
+        self.sell_in = sell_in;
+        self
+    }
 
-            // ...and WHEN updating the quality many times
-            for _ in 1..=999 {
-                rose.update_quality();
+    pub fn quality(mut self, quality: i32) -> ItemBuilder {
+        self.quality = quality;
+        self
+    }
+
+    /// Legendary items must have a quality of exactly 80, all other items a quality between 0
+    /// and 50. Names are classified the way the shop does, ignoring stray whitespace.
+    pub fn build(self) -> Result<Item, ItemError> {
+        let name = self.name.ok_or(ItemError::MissingName)?;
+
+        if ItemKind::from_name(&resolve_name(&name)) == ItemKind::Legendary {
+            if self.quality != LEGENDARY_QUALITY {
+                return Err(ItemError::LegendaryQuality { quality: self.quality });
             }
+        } else if self.quality < MINIMUM_ALLOWED_QUALITY || self.quality > MAXIMUM_ALLOWED_QUALITY {
+            return Err(ItemError::QualityOutOfRange { quality: self.quality });
+        }
 
-            // THEN nothing should change
-            assert_eq!(1, rose.items[0].sell_in);
-            assert_eq!(80, rose.items[0].quality);
+        Ok(Item::new(name, self.sell_in, self.quality))
+    }
+}
 
-            assert_eq!(0, rose.items[1].sell_in);
-            assert_eq!(80, rose.items[1].quality);
+/// Why an `ItemBuilder` refused to build an item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemError {
+    MissingName,
+    QualityOutOfRange { quality: i32 },
+    LegendaryQuality { quality: i32 },
+}
 
-            assert_eq!(-1, rose.items[2].sell_in);
-            assert_eq!(80, rose.items[2].quality);
+impl Display for ItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemError::MissingName => write!(f, "an item needs a name"),
+            ItemError::QualityOutOfRange { quality } => write!(
+                f, "quality {} is outside {}..={}", quality, MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY
+            ),
+            ItemError::LegendaryQuality { quality } => write!(
+                f, "legendary items have quality {}, not {}", LEGENDARY_QUALITY, quality
+            ),
         }
+    }
+}
 
-        #[test]
-        fn test_quality_increasing_items() {
-            // GIVEN items of which quality increases when it gets older
-            let items = vec![
-                Item::new(AGED_BRIE_ITEM, 2, 0),
-                Item::new(AGED_BRIE_ITEM, 4, 49),
-            ];
-            let mut rose = GildedRose::new(items);
-
-            // WHEN updating quality
-            rose.update_quality();
+impl Error for ItemError {}
 
-            // THEN quality should increase
-            assert_eq!(1, rose.items[0].sell_in);
-            assert_eq!(1, rose.items[0].quality);
 
-            assert_eq!(50, rose.items[1].quality);
+/// A quality checked against the default bounds of 0 to 50 when created. Legendary items sit
+/// outside those bounds and keep using a plain `i32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Quality(i32);
 
-            // ...and WHEN updating quality again
-            rose.update_quality();
+impl Quality {
+    pub fn new(quality: i32) -> Result<Quality, QualityError> {
+        if !(MINIMUM_ALLOWED_QUALITY..=MAXIMUM_ALLOWED_QUALITY).contains(&quality) {
+            return Err(QualityError::OutOfRange { quality });
+        }
+        Ok(Quality(quality))
+    }
 
-            // THEN quality should increase but be limited by 50
-            assert_eq!(0, rose.items[0].sell_in);
-            assert_eq!(2, rose.items[0].quality);
+    /// Adjusts the quality by `delta` without overflowing, keeping the result between `min` and
+    /// `max`. Bounds outside the allowed 0 to 50 are narrowed to it, so the result is always a
+    /// valid quality.
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn saturating_adjust(self, delta: i32, min: i32, max: i32) -> Quality {
+        assert!(min <= max, "minimum quality {} is greater than maximum quality {}", min, max);
+        let min = min.clamp(MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY);
+        let max = max.clamp(MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY);
+        Quality(self.0.saturating_add(delta).clamp(min, max))
+    }
+}
 
-            assert_eq!(50, rose.items[1].quality);
+impl Deref for Quality {
+    type Target = i32;
 
-            // ...and WHEN updating quality again so sell in becomes negative
-            rose.update_quality();
+    fn deref(&self) -> &i32 {
+        &self.0
+    }
+}
 
-            // THEN quality should increase by 2
-            assert_eq!(-1, rose.items[0].sell_in);
-            assert_eq!(4, rose.items[0].quality);
+impl From<Quality> for i32 {
+    fn from(quality: Quality) -> i32 {
+        quality.0
+    }
+}
 
-            // ...and WHEN updating quality again when sell in is already negative
-            rose.update_quality();
+/// Why `Quality::new` refused a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QualityError {
+    OutOfRange { quality: i32 },
+}
 
-            // THEN quality should continue to increase by 2
-            assert_eq!(6, rose.items[0].quality);
+impl Display for QualityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QualityError::OutOfRange { quality } => write!(
+                f, "quality {} is outside {}..={}", quality, MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY
+            ),
         }
+    }
+}
 
-        #[test]
-        fn test_quality_zero_after_sell_in_items() {
-            // GIVEN items of which quality increases more towards sell-in date and becomes 0 after
-            let items = vec![
-                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
-                Item::new(BACKSTAGE_PASSES_ITEM, 10, 0),
-                Item::new(BACKSTAGE_PASSES_ITEM, 5, 48),
-            ];
-            let mut rose = GildedRose::new(items);
-
-            // WHEN updating quality
-            rose.update_quality();
+impl Error for QualityError {}
 
-            // THEN quality should increase
-            assert_eq!(14, rose.items[0].sell_in);
-            assert_eq!(21, rose.items[0].quality);
 
-            assert_eq!(9, rose.items[1].sell_in);
-            assert_eq!(2, rose.items[1].quality); // increase by 2 when <= 10 days left
+/// The built-in behaviors an item can have, classified from its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    AgedBrie,
+    BackstagePass,
+    Conjured,
+    Legendary,
+    Common,
+}
 
-            assert_eq!(4, rose.items[2].sell_in);
-            assert_eq!(50, rose.items[2].quality); // not increase past 50
+impl ItemKind {
+    /// Classifies `name` by the canonical item names. Any name starting with `"Conjured "` is
+    /// conjured and every other name is common.
+    pub fn from_name(name: &str) -> ItemKind {
+        match name {
+            AGED_BRIE_ITEM => ItemKind::AgedBrie,
+            BACKSTAGE_PASSES_ITEM => ItemKind::BackstagePass,
+            LEGENDARY_ITEM => ItemKind::Legendary,
+            _ if base_name(name).1 => ItemKind::Conjured,
+            _ => ItemKind::Common,
+        }
+    }
 
-            // ...and WHEN updating quality 4 more times
-            for _ in 1..=4 {
-                rose.update_quality();
-            }
+    /// Which way this kind's built-in behavior moves `item`'s quality on the next update: `1` up,
+    /// `-1` down or `0` not at all, ignoring the quality bounds. Aged brie and backstage passes
+    /// before the concert go up, common items go down, legendary items stay put, and backstage
+    /// passes after the concert go down until they reach 0. Conjured items go the way of the item
+    /// named by the rest of `item`'s name, like `daily_quality_delta` has them.
+    pub fn quality_direction(self, item: &Item) -> i8 {
+        let name = resolve_name(&item.name);
+        let strategy = ResolvedStrategy::builtin(self, ItemKind::from_name(base_name(&name).0));
+        let (_, quality) = strategy.next_state(item, i32::MIN, i32::MAX);
+        match quality.cmp(&item.quality) {
+            Ordering::Greater => 1,
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+        }
+    }
 
-            // THEN quality should increase 4 more times
-            assert_eq!(10, rose.items[0].sell_in);
-            assert_eq!(25, rose.items[0].quality);
+    /// The `(sell_in, quality)` that `Item::fresh` gives items of this kind.
+    fn fresh_state(self) -> (i32, i32) {
+        match self {
+            ItemKind::AgedBrie => (10, 0),
+            ItemKind::BackstagePass => (15, 20),
+            ItemKind::Conjured => (5, 20),
+            ItemKind::Legendary => (0, LEGENDARY_QUALITY),
+            ItemKind::Common => (10, 20),
+        }
+    }
 
-            assert_eq!(5, rose.items[1].sell_in);
-            assert_eq!(10, rose.items[1].quality); // increase by 4 x 2 = 8 when <= 10 days left
+    fn strategy(self) -> &'static dyn UpdateStrategy {
+        match self {
+            ItemKind::AgedBrie => &DEFAULT_AGED_BRIE_STRATEGY,
+            ItemKind::BackstagePass => &DEFAULT_BACKSTAGE_PASS_STRATEGY,
+            ItemKind::Conjured => &DEFAULT_CONJURED_STRATEGY,
+            ItemKind::Legendary => &LegendaryStrategy,
+            ItemKind::Common => &DEFAULT_COMMON_STRATEGY,
+        }
+    }
+}
 
-            assert_eq!(0, rose.items[2].sell_in);
-            assert_eq!(50, rose.items[2].quality); // not increase past 50
 
-            // ...and WHEN updating 1 more time
-            rose.update_quality();
+/// The names a shop recognizes its special items by, for shops that rename them. Defaults to
+/// the canonical names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameConfig {
+    pub aged_brie: String,
+    pub backstage_passes: String,
+    pub legendary: String,
+    /// Names starting with this are conjured versions of the item named by the rest.
+    pub conjured_prefix: String,
+}
 
-            // THEN quality should become 0 "after the concert"
-            assert_eq!(-1, rose.items[2].sell_in);
-            assert_eq!(0, rose.items[2].quality);
+impl NameConfig {
+    /// Classifies `name` by these names, ignoring ASCII case when `ignore_case` is set.
+    fn kind_of(&self, name: &str, ignore_case: bool) -> ItemKind {
+        let matches = |known: &str| if ignore_case { name.eq_ignore_ascii_case(known) } else { name == known };
+        if matches(&self.aged_brie) {
+            ItemKind::AgedBrie
+        } else if matches(&self.backstage_passes) {
+            ItemKind::BackstagePass
+        } else if matches(&self.legendary) {
+            ItemKind::Legendary
+        } else if self.base_name(name, ignore_case).1 {
+            ItemKind::Conjured
+        } else {
+            ItemKind::Common
+        }
+    }
 
-            // ...and WHEN updating 1 more time
-            rose.update_quality();
+    /// Splits the conjured prefix off `name` like `base_name`, ignoring ASCII case when
+    /// `ignore_case` is set.
+    fn base_name<'a>(&self, name: &'a str, ignore_case: bool) -> (&'a str, bool) {
+        let prefix_len = self.conjured_prefix.len();
+        match name.get(..prefix_len) {
+            Some(prefix) if prefix == self.conjured_prefix => (&name[prefix_len..], true),
+            Some(prefix) if ignore_case && prefix.eq_ignore_ascii_case(&self.conjured_prefix) => {
+                (&name[prefix_len..], true)
+            },
+            _ => (name, false),
+        }
+    }
+}
 
-            // THEN quality should stay 0 "after the concert"
-            assert_eq!(-2, rose.items[2].sell_in);
-            assert_eq!(0, rose.items[2].quality);
+impl Default for NameConfig {
+    fn default() -> NameConfig {
+        NameConfig {
+            aged_brie: AGED_BRIE_ITEM.to_string(),
+            backstage_passes: BACKSTAGE_PASSES_ITEM.to_string(),
+            legendary: LEGENDARY_ITEM.to_string(),
+            conjured_prefix: CONJURED_PREFIX.to_string(),
         }
     }
+}
 
-    mod new_conjured_item_feature {
-        use gildedrose::tests::{CONJURED_ITEM};
-        use super::{GildedRose, Item};
 
-        #[test]
-        fn test_conjured_item_quality_update() {
-            // GIVEN a conjured item
-            let items = vec![
+/// The bounds every `UpdateStrategy` has to meet: `Send` and `Sync` with the `rayon` feature, as
+/// strategies are shared between threads when items are updated in parallel, and none without
+/// it. Every type meeting them implements it.
+#[cfg(feature = "rayon")]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(feature = "rayon")]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// The bounds every `UpdateStrategy` has to meet: `Send` and `Sync` with the `rayon` feature, as
+/// strategies are shared between threads when items are updated in parallel, and none without
+/// it. Every type meeting them implements it.
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSendSync {}
+
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Decides how an item changes over a single day, returning its next `(sell_in, quality)`. The
+/// quality is kept within `min` and `max` unless the strategy has a reason not to.
+pub trait UpdateStrategy: MaybeSendSync {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32);
+
+    /// Like `next_state`, but with the faster rate of expired items only starting once `sell_in`
+    /// is at or below `expiry_threshold` instead of 0. Strategies without such a rate ignore the
+    /// threshold, which is what the default does.
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, _expiry_threshold: i32) -> (i32, i32) {
+        self.next_state(item, min, max)
+    }
+
+    /// Whether the next update sets `item`'s quality to a fixed value, like backstage passes
+    /// dropping to 0 after the concert, rather than adjusting it. Conjured items take such a
+    /// value as it is instead of multiplying the change.
+    fn sets_quality(&self, _item: &Item) -> bool {
+        false
+    }
+}
+
+/// Borrowed strategies work as strategies, for wrapping one without taking ownership of it.
+impl<'a> UpdateStrategy for &'a (dyn UpdateStrategy + 'a) {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        (**self).next_state(item, min, max)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        (**self).next_state_with_expiry(item, min, max, expiry_threshold)
+    }
+
+    fn sets_quality(&self, item: &Item) -> bool {
+        (**self).sets_quality(item)
+    }
+}
+
+/// Boxed strategies work as strategies, like the ones registered on a shop.
+impl<'a> UpdateStrategy for Box<dyn UpdateStrategy + 'a> {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        (**self).next_state(item, min, max)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        (**self).next_state_with_expiry(item, min, max, expiry_threshold)
+    }
+
+    fn sets_quality(&self, item: &Item) -> bool {
+        (**self).sets_quality(item)
+    }
+}
+
+/// A closure returning an item's next quality works as a strategy, for ad-hoc rules. `sell_in`
+/// counts down by one and the quality is kept within the bounds. Boxed for
+/// `GildedRose::register_strategy`, the closure needs its argument typed, as in
+/// `|item: &Item| item.quality / 2`; `GildedRose::register_fn` takes one without.
+impl<F> UpdateStrategy for F where F: Fn(&Item) -> i32 + MaybeSendSync {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        (item.sell_in.saturating_sub(1), self(item).clamp(min, max))
+    }
+}
+
+static DEFAULT_AGED_BRIE_STRATEGY: AgedBrieStrategy = AgedBrieStrategy { ceiling: None };
+
+/// Aged brie gains quality as it ages, up to the shop's maximum quality unless the strategy has
+/// a ceiling of its own. From the update made at `sell_in` 0, its last day of sale, it gains 2
+/// a day instead of 1, just as common items start losing twice as much then.
+#[derive(Clone, Debug, Default)]
+pub struct AgedBrieStrategy {
+    ceiling: Option<i32>,
+}
+
+impl AgedBrieStrategy {
+    pub fn new() -> AgedBrieStrategy {
+        AgedBrieStrategy::default()
+    }
+
+    /// Caps quality at `ceiling` instead of the shop's maximum quality, which may be above or
+    /// below it.
+    pub fn with_ceiling(ceiling: i32) -> AgedBrieStrategy {
+        AgedBrieStrategy { ceiling: Some(ceiling) }
+    }
+}
+
+impl UpdateStrategy for AgedBrieStrategy {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        self.next_state_with_expiry(item, min, max, 0)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        // `<=` rather than `<`: the boost starts with the update made at `sell_in` 0.
+        let quality_adjustment =
+            if item.sell_in <= expiry_threshold {
+                2
+            } else {
+                1
+            };
+        let max = self.ceiling.unwrap_or(max);
+        (item.sell_in.saturating_sub(1), get_updated_quality_within_bounds(item, quality_adjustment, min, max))
+    }
+}
+
+/// What happens to backstage passes once the concert is over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PostConcert {
+    /// Quality drops to 0 right after the concert.
+    #[default]
+    Zero,
+    /// Quality drops by the given amount every day after the concert.
+    Linear(i32),
+}
+
+/// What a shop does when an item's strategy has to keep its quality within the bounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClampPolicy {
+    /// Quality is kept at the bound it would pass.
+    #[default]
+    Saturate,
+    /// The quality change is rejected, leaving the quality as it was.
+    Reject,
+    /// Quality is set to the given value instead, such as 0 to write such items off.
+    Reset(i32),
+}
+
+/// The `(days_remaining_threshold, quality_gain)` tiers of regular backstage passes: +3 when
+/// there are 5 days or less and +2 when there are 10 days or less.
+const DEFAULT_BACKSTAGE_TIERS: &[(i32, i32)] = &[(5, 3), (10, 2)];
+
+static DEFAULT_BACKSTAGE_PASS_STRATEGY: BackstagePassStrategy = BackstagePassStrategy {
+    tiers: Cow::Borrowed(DEFAULT_BACKSTAGE_TIERS),
+    post_concert: PostConcert::Zero,
+};
+
+/// Backstage passes gain quality faster as the concert approaches. The gain is that of the
+/// tier with the smallest threshold that `sell_in` is at or below, or 1 when `sell_in` is above
+/// every threshold.
+#[derive(Clone, Debug)]
+pub struct BackstagePassStrategy {
+    tiers: Cow<'static, [(i32, i32)]>,
+    post_concert: PostConcert,
+}
+
+impl BackstagePassStrategy {
+    pub fn new() -> BackstagePassStrategy {
+        DEFAULT_BACKSTAGE_PASS_STRATEGY.clone()
+    }
+
+    pub fn with_post_concert(post_concert: PostConcert) -> BackstagePassStrategy {
+        BackstagePassStrategy { post_concert, ..BackstagePassStrategy::new() }
+    }
+
+    /// Uses `(days_remaining_threshold, quality_gain)` tiers instead of the regular ones, in
+    /// any order.
+    pub fn with_tiers(mut tiers: Vec<(i32, i32)>) -> BackstagePassStrategy {
+        tiers.sort_by_key(|&(threshold, _)| threshold);
+        BackstagePassStrategy { tiers: Cow::Owned(tiers), ..BackstagePassStrategy::new() }
+    }
+}
+
+impl Default for BackstagePassStrategy {
+    fn default() -> BackstagePassStrategy {
+        BackstagePassStrategy::new()
+    }
+}
+
+impl UpdateStrategy for BackstagePassStrategy {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        if item.sell_in <= 0 {
+            let quality = match self.post_concert {
+                PostConcert::Zero => 0.clamp(min, max),
+                PostConcert::Linear(loss) => get_updated_quality_within_bounds(item, -loss, min, max),
+            };
+            return (item.sell_in.saturating_sub(1), quality)
+        }
+
+        let quality_adjustment = self.tiers.iter()
+            .find(|&&(threshold, _)| item.sell_in <= threshold)
+            .map_or(1, |&(_, gain)| gain);
+        (item.sell_in.saturating_sub(1), get_updated_quality_within_bounds(item, quality_adjustment, min, max))
+    }
+
+    fn sets_quality(&self, item: &Item) -> bool {
+        item.sell_in <= 0 && self.post_concert == PostConcert::Zero
+    }
+}
+
+/// How many times as fast conjured items change quality by default.
+const DEFAULT_CONJURED_MULTIPLIER: i32 = 2;
+
+static DEFAULT_CONJURED_STRATEGY: ConjuredStrategy<DefaultStrategy> = ConjuredStrategy {
+    base: DefaultStrategy { decay_tiers: Cow::Borrowed(DEFAULT_DECAY_TIERS) },
+    multiplier: DEFAULT_CONJURED_MULTIPLIER,
+};
+
+/// Wraps the strategy of the item a conjured item is a version of, changing its quality
+/// `multiplier` times as fast, twice as fast by default. Both losses and gains are multiplied,
+/// so conjured aged brie gains twice as much, but quality the base strategy sets to a fixed
+/// value, like backstage passes after the concert, is taken as it is. Shops follow one for
+/// conjured names without a registration of their own.
+#[derive(Clone, Debug)]
+pub struct ConjuredStrategy<S> {
+    base: S,
+    multiplier: i32,
+}
+
+impl<S: UpdateStrategy> ConjuredStrategy<S> {
+    pub fn new(base: S) -> ConjuredStrategy<S> {
+        ConjuredStrategy::with_multiplier(base, DEFAULT_CONJURED_MULTIPLIER)
+    }
+
+    pub fn with_multiplier(base: S, multiplier: i32) -> ConjuredStrategy<S> {
+        ConjuredStrategy { base, multiplier }
+    }
+}
+
+/// Conjured common items, which degrade twice as fast as common items.
+impl Default for ConjuredStrategy<DefaultStrategy> {
+    fn default() -> ConjuredStrategy<DefaultStrategy> {
+        ConjuredStrategy::new(DefaultStrategy::new())
+    }
+}
+
+impl<S: UpdateStrategy> UpdateStrategy for ConjuredStrategy<S> {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        self.next_state_with_expiry(item, min, max, 0)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        multiplied_state(&self.base, item, min, max, expiry_threshold, self.multiplier)
+    }
+
+    fn sets_quality(&self, item: &Item) -> bool {
+        self.base.sets_quality(item)
+    }
+}
+
+/// Legendary items never have to be sold, so neither their `sell_in` nor their quality ever
+/// changes, even when the quality is outside the quality bounds.
+pub struct LegendaryStrategy;
+
+impl UpdateStrategy for LegendaryStrategy {
+    fn next_state(&self, item: &Item, _min: i32, _max: i32) -> (i32, i32) {
+        (item.sell_in, item.quality)
+    }
+}
+
+/// The `(sell_in_threshold, rate_multiplier)` tiers of regular common items: twice the degrade
+/// rate from the last day of sale on.
+const DEFAULT_DECAY_TIERS: &[(i32, i32)] = &[(0, 2)];
+
+static DEFAULT_COMMON_STRATEGY: DefaultStrategy = DefaultStrategy {
+    decay_tiers: Cow::Borrowed(DEFAULT_DECAY_TIERS),
+};
+
+/// Common items lose their degrade rate every day, multiplied by that of the tier with the
+/// smallest threshold that `sell_in` is at or below, or by 1 when `sell_in` is above every
+/// threshold. The thresholds count from the shop's expiry threshold, which is 0 by default.
+#[derive(Clone, Debug)]
+pub struct DefaultStrategy {
+    decay_tiers: Cow<'static, [(i32, i32)]>,
+}
+
+impl DefaultStrategy {
+    pub fn new() -> DefaultStrategy {
+        DEFAULT_COMMON_STRATEGY.clone()
+    }
+
+    /// Uses `(sell_in_threshold, rate_multiplier)` tiers instead of the regular ones, in any
+    /// order, for perishables that decay faster the longer they are expired.
+    pub fn with_decay_tiers(mut tiers: Vec<(i32, i32)>) -> DefaultStrategy {
+        tiers.sort_by_key(|&(threshold, _)| threshold);
+        DefaultStrategy { decay_tiers: Cow::Owned(tiers) }
+    }
+}
+
+impl Default for DefaultStrategy {
+    fn default() -> DefaultStrategy {
+        DefaultStrategy::new()
+    }
+}
+
+impl UpdateStrategy for DefaultStrategy {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        self.next_state_with_expiry(item, min, max, 0)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        let degrade_rate = item.degrade_rate.unwrap_or(1);
+        let rate_multiplier = self.decay_tiers.iter()
+            .find(|&&(threshold, _)| item.sell_in <= threshold.saturating_add(expiry_threshold))
+            .map_or(1, |&(_, multiplier)| multiplier);
+        let quality_adjustment = degrade_rate.saturating_mul(rate_multiplier.saturating_neg());
+        (item.sell_in.saturating_sub(1), get_updated_quality_within_bounds(item, quality_adjustment, min, max))
+    }
+}
+
+/// Perishable premium items gain 1 quality a day while `sell_in` is above `peak_sell_in`, then
+/// lose 1 a day once they are past their peak.
+#[derive(Clone, Debug)]
+pub struct PeakStrategy {
+    peak_sell_in: i32,
+}
+
+impl PeakStrategy {
+    pub fn new(peak_sell_in: i32) -> PeakStrategy {
+        PeakStrategy { peak_sell_in }
+    }
+}
+
+impl UpdateStrategy for PeakStrategy {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        let quality_adjustment = if item.sell_in > self.peak_sell_in { 1 } else { -1 };
+        (item.sell_in.saturating_sub(1), get_updated_quality_within_bounds(item, quality_adjustment, min, max))
+    }
+}
+
+/// Splits a `"Conjured "` prefix off `name`, returning the underlying item name and whether the
+/// prefix was present.
+fn base_name(name: &str) -> (&str, bool) {
+    match name.strip_prefix(CONJURED_PREFIX) {
+        Some(base) => (base, true),
+        None => (name, false),
+    }
+}
+
+/// The form of `name` used to pick a built-in behavior: the name without surrounding
+/// whitespace and with every run of whitespace inside it collapsed to a single space, so
+/// `"Conjured  Aged Brie"` is conjured aged brie. The item keeps its name as given.
+fn resolve_name(name: &str) -> Cow<'_, str> {
+    let name = name.trim();
+    let mut previous_whitespace = false;
+    let collapsed = name.chars().all(|c| {
+        let collapsed = !c.is_whitespace() || (c == ' ' && !previous_whitespace);
+        previous_whitespace = c.is_whitespace();
+        collapsed
+    });
+
+    if collapsed {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(name.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Finds the built-in name that `name` only differs from in case or surrounding whitespace.
+fn resembled_name(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    let prefix = name.get(..CONJURED_PREFIX.len());
+    if prefix.is_some_and(|prefix| prefix.eq_ignore_ascii_case(CONJURED_PREFIX)) {
+        return Some(CONJURED_PREFIX);
+    }
+
+    [AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM, LEGENDARY_ITEM].iter()
+        .find(|known| name.eq_ignore_ascii_case(known))
+        .cloned()
+}
+
+/// Multiplies the quality adjustment `inner` makes to `item` by `multiplier`. The adjustment is
+/// taken before `inner` clamps it, so it is multiplied in full even when the item is at or
+/// beyond a bound. Quality that `inner` sets to a fixed value is not multiplied.
+fn multiplied_state(
+    inner: &dyn UpdateStrategy, item: &Item, min: i32, max: i32, expiry_threshold: i32, multiplier: i32,
+) -> (i32, i32) {
+    if inner.sets_quality(item) {
+        return inner.next_state_with_expiry(item, min, max, expiry_threshold);
+    }
+    let (sell_in, unclamped_quality) = inner.next_state_with_expiry(item, i32::MIN, i32::MAX, expiry_threshold);
+    let quality_adjustment = unclamped_quality.saturating_sub(item.quality);
+    (sell_in, get_updated_quality_within_bounds(item, quality_adjustment.saturating_mul(multiplier), min, max))
+}
+
+/// The strategy an item follows, as worked out from its name. Conjured items without a strategy
+/// of their own follow a `ConjuredStrategy` wrapping the strategy for the rest of their name.
+enum ResolvedStrategy<'a> {
+    Plain(&'a dyn UpdateStrategy),
+    Conjured(ConjuredStrategy<&'a dyn UpdateStrategy>),
+}
+
+impl ResolvedStrategy<'static> {
+    /// The built-in strategy for items of `kind` whose name without any conjured prefix is of
+    /// `base_kind`.
+    fn builtin(kind: ItemKind, base_kind: ItemKind) -> ResolvedStrategy<'static> {
+        match kind {
+            ItemKind::Conjured => ResolvedStrategy::Conjured(ConjuredStrategy::new(base_kind.strategy())),
+            kind => ResolvedStrategy::Plain(kind.strategy()),
+        }
+    }
+}
+
+impl<'a> UpdateStrategy for ResolvedStrategy<'a> {
+    fn next_state(&self, item: &Item, min: i32, max: i32) -> (i32, i32) {
+        self.next_state_with_expiry(item, min, max, 0)
+    }
+
+    fn next_state_with_expiry(&self, item: &Item, min: i32, max: i32, expiry_threshold: i32) -> (i32, i32) {
+        match *self {
+            ResolvedStrategy::Plain(strategy) => strategy.next_state_with_expiry(item, min, max, expiry_threshold),
+            ResolvedStrategy::Conjured(ref strategy) => strategy.next_state_with_expiry(item, min, max, expiry_threshold),
+        }
+    }
+
+    fn sets_quality(&self, item: &Item) -> bool {
+        match *self {
+            ResolvedStrategy::Plain(strategy) => strategy.sets_quality(item),
+            ResolvedStrategy::Conjured(ref strategy) => strategy.sets_quality(item),
+        }
+    }
+}
+
+fn get_updated_quality_within_bounds(item: &Item, adjust_by: i32, min: i32, max: i32) -> i32 {
+    item.quality.saturating_add(adjust_by).clamp(min, max)
+}
+
+
+/// How a single item moved during one update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemChange {
+    pub name: String,
+    pub sell_in_before: i32,
+    pub sell_in_after: i32,
+    pub quality_before: i32,
+    pub quality_after: i32,
+}
+
+/// How the item at `index` differs between two shops, as reported by `GildedRose::diff`.
+/// Deltas are the other shop's value minus this shop's.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemDiff {
+    Changed { index: usize, name: String, sell_in_delta: i32, quality_delta: i32 },
+    /// The other shop has an item at an index this shop does not, or a differently named one.
+    AddedInOther { index: usize, item: Item },
+    /// This shop has an item at an index the other shop does not, or a differently named one.
+    RemovedInOther { index: usize, item: Item },
+}
+
+
+/// Counts from one run of `GildedRose::update_quality_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    /// Items whose quality would have gone below the minimum.
+    pub clamped_to_min: usize,
+    /// Items whose quality would have gone above the maximum.
+    pub clamped_to_max: usize,
+    /// Items whose `sell_in` went from 0 or more to below 0.
+    pub expired_this_tick: usize,
+}
+
+
+/// A copy of a shop's items taken by `GildedRose::snapshot`, to be put back with
+/// `GildedRose::restore`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InventorySnapshot {
+    items: Vec<Item>,
+    /// The ids the items had, as far as they had one.
+    ids: Vec<u64>,
+}
+
+impl InventorySnapshot {
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+}
+
+
+/// A problem `GildedRose::validate` found with the item at `index`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemValidation {
+    pub index: usize,
+    pub reason: ValidationReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationReason {
+    /// The quality is outside the shop's bounds and would be clamped on the next update.
+    QualityOutOfRange { quality: i32 },
+    /// Legendary items never have to be sold, so they should not be past their sell in date.
+    LegendaryNegativeSellIn { sell_in: i32 },
+    /// The name only differs from a built-in name in case, so the item would be treated as a
+    /// common item.
+    UnknownName { resembles: &'static str },
+}
+
+/// Why `GildedRose::try_update_quality` refused to update: every problem `validate` found, in
+/// item order. There is always at least one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpdateError {
+    pub validations: Vec<ItemValidation>,
+}
+
+impl Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indices: BTreeSet<usize> = self.validations.iter().map(|validation| validation.index).collect();
+        let indices: Vec<String> = indices.iter().map(ToString::to_string).collect();
+        match indices.len() {
+            1 => write!(f, "invalid item at index {}", indices[0]),
+            _ => write!(f, "invalid items at indices {}", indices.join(", ")),
+        }
+    }
+}
+
+impl Error for UpdateError {}
+
+
+/// The SplitMix64 generator, a small pseudo-random generator that is good enough for making up
+/// inventories.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to but not including `bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// How a shop classifies an item's name: the kind of the name and of the name without any
+/// conjured prefix, and the index in the shop's `strategies` of the strategies registered for
+/// either.
+#[derive(Clone, Copy, Debug)]
+struct Classification {
+    kind: ItemKind,
+    base_kind: ItemKind,
+    strategy: Option<usize>,
+    base_strategy: Option<usize>,
+}
+
+/// How far an update moved an item, or the items of a shop at their furthest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Progress {
+    /// Nothing changed, so no later update changes anything either.
+    Unchanged,
+    /// Only `sell_in` counted down, and the quality never changes again: the item follows a
+    /// built-in strategy that no longer depends on `sell_in`.
+    Settled,
+    Changed,
+}
+
+/// Called by `GildedRose::on_update` with the change to each item.
+pub type UpdateCallback = Box<dyn FnMut(&ItemChange)>;
+
+/// Set with `GildedRose::set_middleware`. Called with an item and the `next_quality` and
+/// `next_sell_in` worked out for it, returning the `(quality, sell_in)` to use instead. With the
+/// `rayon` feature it is shared between threads like strategies, so it has to be `Send` and
+/// `Sync`.
+#[cfg(feature = "rayon")]
+pub type Middleware = Box<dyn Fn(&Item, i32, i32) -> (i32, i32) + Send + Sync>;
+
+/// Set with `GildedRose::set_middleware`. Called with an item and the `next_quality` and
+/// `next_sell_in` worked out for it, returning the `(quality, sell_in)` to use instead. With the
+/// `rayon` feature it is shared between threads like strategies, so it has to be `Send` and
+/// `Sync`.
+#[cfg(not(feature = "rayon"))]
+pub type Middleware = Box<dyn Fn(&Item, i32, i32) -> (i32, i32)>;
+
+pub struct GildedRose {
+    pub items: Vec<Item>,
+    min_quality: i32,
+    max_quality: i32,
+    /// Registered strategies, in the order their names were first registered.
+    strategies: Vec<Box<dyn UpdateStrategy>>,
+    /// The index in `strategies` of the strategy registered for each name.
+    strategy_indices: BTreeMap<String, usize>,
+    /// Strategy overrides by item id.
+    overrides: BTreeMap<u64, Box<dyn UpdateStrategy>>,
+    /// The id of the item at the same index. Items pushed onto `items` directly get theirs the
+    /// next time the shop changes its items.
+    ids: Vec<u64>,
+    next_id: u64,
+    on_update: Option<UpdateCallback>,
+    middleware: Option<Middleware>,
+    names: NameConfig,
+    case_insensitive_names: bool,
+    sell_in_floor: Option<i32>,
+    appreciating_max_quality: Option<i32>,
+    expiry_threshold: i32,
+    conjured_multiplier: i32,
+    clamp_policy: ClampPolicy,
+    /// The name the item at the same index had when it was classified, and its classification.
+    /// Items can be renamed through `items` directly, so the names are compared before each
+    /// update and only items whose name changed are classified again.
+    classifications: Vec<(String, Classification)>,
+}
+
+impl GildedRose {
+    pub fn new(items: Vec<Item>) -> GildedRose {
+        GildedRose::with_bounds(items, MINIMUM_ALLOWED_QUALITY, MAXIMUM_ALLOWED_QUALITY)
+    }
+
+    /// Creates a shop from `(name, sell_in, quality)` tuples.
+    pub fn from_tuples<S: Into<String>>(items: impl IntoIterator<Item = (S, i32, i32)>) -> GildedRose {
+        items.into_iter().map(|(name, sell_in, quality)| Item::new(name, sell_in, quality)).collect()
+    }
+
+    /// Creates an empty shop with room for `capacity` items before it needs to reallocate.
+    pub fn with_capacity(capacity: usize) -> GildedRose {
+        GildedRose::new(Vec::with_capacity(capacity))
+    }
+
+    /// Creates a shop with `count` items of the canonical kinds for stress tests and benchmarks.
+    /// `sell_in` is between -10 and 30, and quality is valid for the item's kind. The items only
+    /// depend on `seed`, so the same seed always gives the same inventory.
+    pub fn random(count: usize, seed: u64) -> GildedRose {
+        const NAMES: &[&str] = &[COMMON_ITEM, CONJURED_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM, LEGENDARY_ITEM];
+
+        let mut random = SplitMix64(seed);
+        (0..count).map(|_| {
+            let name = NAMES[random.below(NAMES.len() as u64) as usize];
+            if name == LEGENDARY_ITEM {
+                Item::new(name, random.below(31) as i32, LEGENDARY_QUALITY)
+            } else {
+                let sell_in = random.below(41) as i32 - 10;
+                let quality = random.below(MAXIMUM_ALLOWED_QUALITY as u64 + 1) as i32;
+                Item::new(name, sell_in, quality)
+            }
+        }).collect()
+    }
+
+    /// Creates a shop whose items' quality is kept within `min` and `max` instead of the
+    /// default 0 and 50. Legendary items keep their quality regardless.
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn with_bounds(items: Vec<Item>, min: i32, max: i32) -> GildedRose {
+        assert!(min <= max, "minimum quality {} is greater than maximum quality {}", min, max);
+        let count = items.len() as u64;
+        GildedRose {
+            items,
+            min_quality: min,
+            max_quality: max,
+            strategies: Vec::new(),
+            strategy_indices: BTreeMap::new(),
+            overrides: BTreeMap::new(),
+            ids: (0..count).collect(),
+            next_id: count,
+            on_update: None,
+            middleware: None,
+            names: NameConfig::default(),
+            case_insensitive_names: false,
+            sell_in_floor: None,
+            appreciating_max_quality: None,
+            expiry_threshold: 0,
+            conjured_multiplier: DEFAULT_CONJURED_MULTIPLIER,
+            clamp_policy: ClampPolicy::Saturate,
+            classifications: Vec::new(),
+        }
+    }
+
+    /// Creates a shop that recognizes its special items by `names` instead of the canonical
+    /// names.
+    pub fn with_names(items: Vec<Item>, names: NameConfig) -> GildedRose {
+        GildedRose { names, ..GildedRose::new(items) }
+    }
+
+    /// Makes the built-in names, including the conjured prefix, match regardless of ASCII case,
+    /// so `"AGED BRIE"` and `"conjured  aged brie"` behave like their built-in counterparts. Off
+    /// by default. Registered strategies still need the exact name.
+    pub fn with_case_insensitive_names(mut self, enabled: bool) -> GildedRose {
+        self.case_insensitive_names = enabled;
+        self.classifications.clear();
+        self
+    }
+
+    /// Stops `sell_in` from counting down past `floor`, keeping long simulations from growing it
+    /// without bound. Items already below the floor keep their `sell_in`. `None`, the default,
+    /// lets it count down until `i32::MIN`.
+    pub fn with_sell_in_floor(mut self, floor: Option<i32>) -> GildedRose {
+        self.sell_in_floor = floor;
+        self
+    }
+
+    /// Lets aged brie and backstage passes, including conjured ones, gain quality up to `max`
+    /// instead of the shop's maximum quality, while other items stay capped at the latter.
+    ///
+    /// Panics if `max` is less than the shop's minimum quality.
+    pub fn with_appreciating_max_quality(mut self, max: i32) -> GildedRose {
+        assert!(self.min_quality <= max, "minimum quality {} is greater than maximum quality {}", self.min_quality, max);
+        self.appreciating_max_quality = Some(max);
+        self
+    }
+
+    /// Holds off the faster rate of expired items until `sell_in` is at or below `threshold`
+    /// instead of 0, giving a grace period when it is negative. Backstage passes are not
+    /// affected, as the concert does not move.
+    pub fn with_expiry_threshold(mut self, threshold: i32) -> GildedRose {
+        self.expiry_threshold = threshold;
+        self
+    }
+
+    /// Makes conjured items without a registration of their own change quality `multiplier`
+    /// times as fast as the strategy for the rest of their name, instead of twice as fast.
+    pub fn with_conjured_multiplier(mut self, multiplier: i32) -> GildedRose {
+        self.conjured_multiplier = multiplier;
+        self
+    }
+
+    /// Decides what happens when an item's strategy would take its quality beyond a bound,
+    /// instead of keeping it at the bound. Legendary items and strategies that allow quality
+    /// beyond the shop's bounds, like aged brie with a higher ceiling, are not affected.
+    ///
+    /// Panics if the policy resets quality to a value outside the shop's bounds.
+    pub fn with_clamp_policy(mut self, policy: ClampPolicy) -> GildedRose {
+        if let ClampPolicy::Reset(quality) = policy {
+            assert!(
+                (self.min_quality..=self.max_quality).contains(&quality),
+                "reset quality {} is outside {}..={}", quality, self.min_quality, self.max_quality,
+            );
+        }
+        self.clamp_policy = policy;
+        self
+    }
+
+    /// Makes items named `name` follow `strategy` from the next update on.
+    ///
+    /// Registrations take precedence over the built-in behavior of an `ItemKind`, so registering
+    /// one of the built-in names replaces its behavior; the last registration for a name wins.
+    /// Closures need their argument typed to be boxed here; `register_fn` takes them as they are.
+    pub fn register_strategy(&mut self, name: impl Into<String>, strategy: Box<dyn UpdateStrategy>) {
+        let name = name.into();
+        match self.strategy_indices.get(&name) {
+            Some(&index) => self.strategies[index] = strategy,
+            None => {
+                self.strategy_indices.insert(name, self.strategies.len());
+                self.strategies.push(strategy);
+                self.classifications.clear();
+            },
+        }
+    }
+
+    /// Registers a closure returning an item's next quality as the strategy for items named
+    /// `name`, like `register_strategy`, so `|item| item.quality / 2` works without typing its
+    /// argument.
+    pub fn register_fn(&mut self, name: impl Into<String>, strategy: impl Fn(&Item) -> i32 + MaybeSendSync + 'static) {
+        self.register_strategy(name, Box::new(strategy));
+    }
+
+    /// Makes the item at `index` follow `strategy` from the next update on, taking precedence
+    /// over registrations and the built-in behavior for its name, replacing any earlier override
+    /// for the item. The override goes with the item's id, so it follows the item when items
+    /// are removed or reordered.
+    ///
+    /// Panics if there is no item at `index`.
+    pub fn apply_strategy_override(&mut self, index: usize, strategy: Box<dyn UpdateStrategy>) {
+        self.assign_ids();
+        self.overrides.insert(self.ids[index], strategy);
+    }
+
+    /// Removes the strategy override for the item at `index`, returning it, so the item goes back
+    /// to the strategy for its name. Overrides are also dropped along with their items when
+    /// those leave the shop.
+    pub fn remove_strategy_override(&mut self, index: usize) -> Option<Box<dyn UpdateStrategy>> {
+        self.assign_ids();
+        let id = *self.ids.get(index)?;
+        self.overrides.remove(&id)
+    }
+
+    /// Calls `callback` with the change to every item on each update, in the order of the items,
+    /// replacing any earlier callback.
+    pub fn on_update(&mut self, callback: UpdateCallback) {
+        self.on_update = Some(callback);
+    }
+
+    /// Runs `middleware` on every item's next state from the next update on, after the item's
+    /// strategy and the shop's settings have worked it out, so it can adjust or veto the change.
+    /// Replaces any earlier middleware; without one the next state is used as it is. Frozen and
+    /// legendary items never change, so the middleware is not run for them.
+    pub fn set_middleware(&mut self, middleware: Middleware) {
+        self.middleware = Some(middleware);
+    }
+
+    /// Changes what happens to backstage passes after the concert by registering a
+    /// `BackstagePassStrategy` for them, replacing any earlier registration.
+    pub fn set_backstage_post_concert(&mut self, post_concert: PostConcert) {
+        let strategy = BackstagePassStrategy::with_post_concert(post_concert);
+        let name = self.names.backstage_passes.clone();
+        self.register_strategy(name, Box::new(strategy));
+    }
+
+    /// Looks up the strategy items named `name` follow on an update, falling back to the
+    /// built-in strategy of its `ItemKind` for names that have no registration. Conjured names
+    /// without one get the strategy for the rest of the name with the conjured multiplier
+    /// applied. Strategy overrides for single items are not considered.
+    pub fn strategy_for(&self, name: &str) -> Box<dyn UpdateStrategy + '_> {
+        Box::new(self.classified_strategy(self.classify(name)))
+    }
+
+    /// Adds `item` at the end, giving it the next id.
+    pub fn push(&mut self, item: Item) {
+        self.items.push(item);
+        self.assign_ids();
+    }
+
+    /// Removes and returns the item at `index`, or `None` when there is no such item. The ids of
+    /// the other items stay the same.
+    pub fn remove(&mut self, index: usize) -> Option<Item> {
+        if index < self.items.len() {
+            self.assign_ids();
+            if index < self.classifications.len() {
+                self.classifications.remove(index);
+            }
+            let id = self.ids.remove(index);
+            self.overrides.remove(&id);
+            Some(self.items.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Keeps only the items for which `keep` returns `true`, in their order, like `Vec::retain`.
+    /// The kept items keep their ids and cached classifications.
+    pub fn retain(&mut self, keep: impl FnMut(&Item) -> bool) {
+        let kept: Vec<bool> = self.items.iter().map(keep).collect();
+        self.retain_flagged(&kept);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.ids.clear();
+        self.overrides.clear();
+        self.classifications.clear();
+    }
+
+    /// Moves the items of `other` to the end of this shop, giving them new ids. Bounds,
+    /// strategies and other settings stay those of this shop.
+    pub fn merge(&mut self, other: GildedRose) {
+        self.assign_ids();
+        self.items.extend(other.items);
+        self.assign_ids();
+    }
+
+    /// The id of the item at `index`. Every item gets an id when it joins the shop, which it
+    /// keeps while items are removed or reordered through the shop, and ids are never reused.
+    /// Items pushed onto `items` directly have none until the shop next changes its items;
+    /// changing `items` directly in other ways leaves the ids out of step.
+    pub fn id_of(&self, index: usize) -> Option<u64> {
+        self.ids.get(index).cloned()
+    }
+
+    /// Returns the item with the id `id`, or `None` when it is no longer in the shop.
+    pub fn find_by_id(&self, id: u64) -> Option<&Item> {
+        let index = self.ids.iter().position(|&other| other == id)?;
+        self.items.get(index)
+    }
+
+    /// Combines two shops into one with the items of `a` followed by those of `b`, keeping the
+    /// bounds, strategies and other settings of `a`.
+    pub fn concat(mut a: GildedRose, b: GildedRose) -> GildedRose {
+        a.merge(b);
+        a
+    }
+
+    /// Removes consecutive identical items, like `Vec::dedup`.
+    pub fn dedup(&mut self) {
+        let kept: Vec<bool> = self.items.iter().enumerate()
+            .map(|(index, item)| index == 0 || self.items[index - 1] != *item)
+            .collect();
+        self.retain_flagged(&kept);
+    }
+
+    /// Removes every item identical to an earlier one, wherever it is.
+    pub fn dedup_all(&mut self) {
+        let mut seen = BTreeSet::new();
+        let kept: Vec<bool> = self.items.iter().map(|item| seen.insert(item)).collect();
+        self.retain_flagged(&kept);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The lowest quality the shop keeps items at, 0 unless set with `with_bounds`.
+    pub fn min_quality(&self) -> i32 {
+        self.min_quality
+    }
+
+    /// The highest quality the shop keeps items at, 50 unless set with `with_bounds`. Legendary
+    /// items and appreciating items with their own maximum can go above it.
+    pub fn max_quality(&self) -> i32 {
+        self.max_quality
+    }
+
+    /// Iterates over the items in order, from either end.
+    pub fn items_iter(&self) -> impl DoubleEndedIterator<Item = &Item> + ExactSizeIterator {
+        self.items.iter()
+    }
+
+    /// Returns the item at `index`, or `None` when there is no such item.
+    pub fn get(&self, index: usize) -> Option<&Item> {
+        self.items.get(index)
+    }
+
+    /// Returns the item at `index` for changing it in place, or `None` when there is no such
+    /// item.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Item> {
+        self.items.get_mut(index)
+    }
+
+    /// Returns the first item named `name`.
+    pub fn find(&self, name: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// Returns the first item named `name` for changing it in place.
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Item> {
+        self.items.iter_mut().find(|item| item.name == name)
+    }
+
+    /// Returns every item named `name`, in inventory order.
+    pub fn items_with_name(&self, name: &str) -> Vec<&Item> {
+        self.items.iter().filter(|item| item.name == name).collect()
+    }
+
+    /// Returns every item past its sell by date, in inventory order.
+    pub fn expired_items(&self) -> Vec<&Item> {
+        self.items.iter().filter(|item| item.is_expired()).collect()
+    }
+
+    /// Copies the items and their ids so they can be put back later with `restore`.
+    pub fn snapshot(&self) -> InventorySnapshot {
+        let ids = self.ids.iter().take(self.items.len()).cloned().collect();
+        InventorySnapshot { items: self.items.clone(), ids }
+    }
+
+    /// Replaces the items with those of `snapshot`, which get back the ids they had. Strategy
+    /// overrides of items that are in the snapshot are kept and the others dropped. Strategies,
+    /// bounds and the `on_update` callback are left as they are.
+    pub fn restore(&mut self, snapshot: InventorySnapshot) {
+        self.items = snapshot.items;
+        self.ids = snapshot.ids;
+        let ids = &self.ids;
+        self.overrides.retain(|id, _| ids.contains(id));
+        self.next_id = self.ids.iter().map(|&id| id + 1).fold(self.next_id, u64::max);
+        self.classifications.clear();
+        self.assign_ids();
+    }
+
+    /// Compares the items with those of `other` index by index. Items that are equal are left
+    /// out, items with the same name at the same index are `Changed`, and a differently named
+    /// item counts as removed and added.
+    pub fn diff(&self, other: &GildedRose) -> Vec<ItemDiff> {
+        let mut diffs = Vec::new();
+        for index in 0..self.items.len().max(other.items.len()) {
+            match (self.items.get(index), other.items.get(index)) {
+                (Some(ours), Some(theirs)) if ours == theirs => {},
+                (Some(ours), Some(theirs)) if ours.name == theirs.name => diffs.push(ItemDiff::Changed {
+                    index,
+                    name: ours.name.clone(),
+                    sell_in_delta: theirs.sell_in.saturating_sub(ours.sell_in),
+                    quality_delta: theirs.quality.saturating_sub(ours.quality),
+                }),
+                (ours, theirs) => {
+                    if let Some(item) = ours {
+                        diffs.push(ItemDiff::RemovedInOther { index, item: item.clone() });
+                    }
+                    if let Some(item) = theirs {
+                        diffs.push(ItemDiff::AddedInOther { index, item: item.clone() });
+                    }
+                },
+            }
+        }
+        diffs
+    }
+
+    /// Sorts the items most valuable first, following the ordering of `Item`.
+    pub fn sort_by_quality(&mut self) {
+        self.assign_ids();
+        let mut items: Vec<(Item, u64)> = mem::take(&mut self.items).into_iter().zip(mem::take(&mut self.ids)).collect();
+        items.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let (items, ids) = items.into_iter().unzip();
+        self.items = items;
+        self.ids = ids;
+        self.classifications.clear();
+    }
+
+    /// Groups the items by the kind the shop classifies them as, keeping inventory order within
+    /// each group. Registered strategies do not change an item's kind.
+    #[cfg(feature = "std")]
+    pub fn group_by_kind(&self) -> HashMap<ItemKind, Vec<&Item>> {
+        let mut groups: HashMap<ItemKind, Vec<&Item>> = HashMap::new();
+        for item in &self.items {
+            groups.entry(self.kind_of(&item.name)).or_default().push(item);
+        }
+        groups
+    }
+
+    /// Counts the items of each kind the shop classifies them as. Kinds without items are left
+    /// out.
+    #[cfg(feature = "std")]
+    pub fn count_by_kind(&self) -> HashMap<ItemKind, usize> {
+        let mut counts = HashMap::new();
+        for item in &self.items {
+            *counts.entry(self.kind_of(&item.name)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Splits the shop into the items that are not expired and those that are, keeping their
+    /// order. The first shop keeps this shop's strategies and settings; the second only its
+    /// quality bounds.
+    pub fn partition_expired(mut self) -> (GildedRose, GildedRose) {
+        self.assign_ids();
+        let fresh_flags: Vec<bool> = self.items.iter().map(|item| !item.is_expired()).collect();
+        self.retain_ids(&fresh_flags);
+        self.classifications.clear();
+        let (expired, fresh): (Vec<Item>, Vec<Item>) = mem::take(&mut self.items).into_iter()
+            .partition(|item| item.is_expired());
+        let expired = GildedRose::with_bounds(expired, self.min_quality, self.max_quality);
+        self.items = fresh;
+        (self, expired)
+    }
+
+    /// Writes the items as a table with a `Name | Sell In | Quality` header, columns as wide as
+    /// their widest value and the numbers aligned to the right. There is no trailing newline.
+    pub fn to_table(&self) -> String {
+        const HEADERS: (&str, &str, &str) = ("Name", "Sell In", "Quality");
+
+        let width = |header: &str, values: &mut dyn Iterator<Item = usize>| values.fold(header.len(), usize::max);
+        let name_width = width(HEADERS.0, &mut self.items.iter().map(|item| item.name.chars().count()));
+        let sell_in_width = width(HEADERS.1, &mut self.items.iter().map(|item| item.sell_in.to_string().len()));
+        let quality_width = width(HEADERS.2, &mut self.items.iter().map(|item| item.quality.to_string().len()));
+
+        let mut table = format!(
+            "{:<name$} | {:>sell_in$} | {:>quality$}\n{}-+-{}-+-{}",
+            HEADERS.0, HEADERS.1, HEADERS.2,
+            "-".repeat(name_width), "-".repeat(sell_in_width), "-".repeat(quality_width),
+            name = name_width, sell_in = sell_in_width, quality = quality_width,
+        );
+        for item in &self.items {
+            table.push_str(&format!(
+                "\n{:<name$} | {:>sell_in$} | {:>quality$}",
+                item.name, item.sell_in, item.quality,
+                name = name_width, sell_in = sell_in_width, quality = quality_width,
+            ));
+        }
+        table
+    }
+
+    /// Returns the item with the highest quality, the one with the soonest `sell_in` among
+    /// equals, or `None` when there are no items.
+    pub fn most_valuable(&self) -> Option<&Item> {
+        self.items.iter().min()
+    }
+
+    /// Returns the item with the lowest quality, the one with the soonest `sell_in` among
+    /// equals, or `None` when there are no items.
+    pub fn least_valuable(&self) -> Option<&Item> {
+        self.items.iter().min_by(|a, b| a.quality.cmp(&b.quality).then(a.sell_in.cmp(&b.sell_in)))
+    }
+
+    /// The smallest `sell_in` in the shop, which is how many days until the next item expires.
+    /// Legendary items never expire, so they only count when `include_legendary` is set. `None`
+    /// when no item counts.
+    pub fn min_sell_in(&self, include_legendary: bool) -> Option<i32> {
+        self.sell_ins(include_legendary).min()
+    }
+
+    /// The largest `sell_in` in the shop, counting legendary items only when
+    /// `include_legendary` is set. `None` when no item counts.
+    pub fn max_sell_in(&self, include_legendary: bool) -> Option<i32> {
+        self.sell_ins(include_legendary).max()
+    }
+
+    /// Counts the items in each `sell_in` range of `bucket_size` days, keyed by the lowest
+    /// `sell_in` in the range, so with a bucket size of 5 the key 0 counts `sell_in` 0 to 4 and
+    /// -5 counts -5 to -1. Ranges without items are left out. Legendary items only count when
+    /// `include_legendary` is set.
+    ///
+    /// Panics if `bucket_size` is not positive.
+    pub fn sell_in_histogram(&self, bucket_size: i32, include_legendary: bool) -> BTreeMap<i32, usize> {
+        assert!(bucket_size > 0, "bucket size {} is not positive", bucket_size);
+        let mut histogram = BTreeMap::new();
+        for sell_in in self.sell_ins(include_legendary) {
+            *histogram.entry(sell_in.div_euclid(bucket_size) * bucket_size).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Sums the quality of all items, including legendary ones.
+    pub fn total_quality(&self) -> i64 {
+        self.items.iter().map(|item| i64::from(item.quality)).sum()
+    }
+
+    /// Sums the quality of all items multiplied by the weight of their kind, where kinds missing
+    /// from `weights` weigh 1.
+    #[cfg(feature = "std")]
+    pub fn weighted_value(&self, weights: &HashMap<ItemKind, f64>) -> f64 {
+        self.items.iter()
+            .map(|item| f64::from(item.quality) * weights.get(&self.kind_of(&item.name)).cloned().unwrap_or(1.0))
+            .sum()
+    }
+
+    /// Averages the quality of all items, or `None` when there are no items.
+    pub fn average_quality(&self) -> Option<f64> {
+        if self.items.is_empty() {
+            return None;
+        }
+        Some(self.total_quality() as f64 / self.items.len() as f64)
+    }
+
+    /// Audits the items without changing them, reporting every problem found in item order.
+    pub fn validate(&self) -> Vec<ItemValidation> {
+        let mut validations = Vec::new();
+        for (index, item) in self.items.iter().enumerate() {
+            let classification = self.classify(&item.name);
+            let kind = classification.kind;
+
+            if kind == ItemKind::Legendary {
+                if item.sell_in < 0 {
+                    let reason = ValidationReason::LegendaryNegativeSellIn { sell_in: item.sell_in };
+                    validations.push(ItemValidation { index, reason });
+                }
+            } else if item.quality < self.min_quality || item.quality > self.max_quality_for(classification.base_kind) {
+                let reason = ValidationReason::QualityOutOfRange { quality: item.quality };
+                validations.push(ItemValidation { index, reason });
+            }
+
+            if kind == ItemKind::Common && classification.strategy.is_none() {
+                if let Some(resembles) = resembled_name(&item.name) {
+                    validations.push(ItemValidation { index, reason: ValidationReason::UnknownName { resembles } });
+                }
+            }
+        }
+        validations
+    }
+
+    /// Names without a registration of their own that start with `"Conjured "` change quality
+    /// twice as fast as the strategy for the rest of the name, or as many times as set with
+    /// `with_conjured_multiplier`, both when losing and when gaining quality.
+    pub fn update_quality(&mut self) {
+        self.advance(None);
+    }
+
+    /// Runs the daily update like `update_quality` if `validate` finds nothing wrong with the
+    /// items, and otherwise leaves them untouched and returns the problems, rather than
+    /// clamping quality or treating look-alike names as common items.
+    pub fn try_update_quality(&mut self) -> Result<(), UpdateError> {
+        let validations = self.validate();
+        if !validations.is_empty() {
+            return Err(UpdateError { validations });
+        }
+        self.update_quality();
+        Ok(())
+    }
+
+    /// Runs the daily update and reports how each item moved, in the order of the items.
+    pub fn update_quality_reporting(&mut self) -> Vec<ItemChange> {
+        let before = self.states();
+        self.refresh_classifications();
+        let mut items = mem::take(&mut self.items);
+        self.update_classified_items(&mut items, None);
+        self.items = items;
+        self.changes_since(before)
+    }
+
+    /// Runs the daily update and counts how many items had their quality limited by a bound and
+    /// how many expired with it. Only the bounds count, not changes made by the clamp policy or
+    /// the middleware afterwards.
+    pub fn update_quality_stats(&mut self) -> UpdateStats {
+        let mut stats = UpdateStats::default();
+        self.advance(Some(&mut stats));
+        stats
+    }
+
+    /// Runs the daily update `days` times, stopping early once the updates can no longer change
+    /// any quality: when an update changes nothing, or when it only counts down the `sell_in` of
+    /// items whose built-in behavior is past every point where it depends on `sell_in`, which is
+    /// then done for all remaining days at once. Either way the items end up as if every update
+    /// had run. The `on_update` callback is not called for days that change nothing, and with a
+    /// callback set every day that counts down `sell_in` is run, so it sees each change.
+    pub fn update_quality_days(&mut self, days: u32) {
+        for day in 1..=days {
+            match self.advance(None) {
+                Progress::Settled if self.on_update.is_none() => {
+                    self.count_down_settled_sell_ins(days - day);
+                    return;
+                },
+                Progress::Changed | Progress::Settled => {},
+                Progress::Unchanged => return,
+            }
+        }
+    }
+
+    /// Shorthand for `update_quality`.
+    pub fn tick(&mut self) {
+        self.update_quality();
+    }
+
+    /// Shorthand for `update_quality_days`.
+    pub fn tick_days(&mut self, days: u32) {
+        self.update_quality_days(days);
+    }
+
+    /// Runs the daily update `days` times, collecting a copy of every item that expired, as it
+    /// was right after the update that expired it. Each copy comes with the number of the update
+    /// that expired it, starting at 1. Items that were already expired are not collected.
+    pub fn advance_collecting_expired(&mut self, days: u32) -> Vec<(u32, Item)> {
+        let mut expired = Vec::new();
+        for day in 1..=days {
+            let was_expired: Vec<bool> = self.items.iter().map(Item::is_expired).collect();
+            self.update_quality();
+            expired.extend(self.items.iter().zip(was_expired)
+                .filter(|&(item, was_expired)| !was_expired && item.is_expired())
+                .map(|(item, _)| (day, item.clone())));
+        }
+        expired
+    }
+
+    /// Runs the daily update until `done` holds for the shop or `max_days` updates have run,
+    /// returning the number of updates run. `done` is checked before every update, so nothing
+    /// happens when it already holds.
+    pub fn apply_until(&mut self, done: impl Fn(&GildedRose) -> bool, max_days: u32) -> u32 {
+        let mut days = 0;
+        while days < max_days && !done(self) {
+            self.update_quality();
+            days += 1;
+        }
+        days
+    }
+
+    /// Returns a copy of the item at `index` as it will be after the next update, without
+    /// changing the shop, or `None` when there is no such item.
+    pub fn peek_next(&self, index: usize) -> Option<Item> {
+        let mut item = self.items.get(index)?.clone();
+        let classification = self.classify(&item.name);
+        self.update_item(&mut item, Some(index), classification, None);
+        Some(item)
+    }
+
+    /// Simulates `days` updates on a copy of the items without changing the shop. The snapshot
+    /// at index `d` is the inventory after `d` updates, so index 0 is the current inventory.
+    pub fn simulate(&self, days: u32) -> Vec<Vec<Item>> {
+        let mut items = self.items.clone();
+        let classifications: Vec<Classification> = items.iter().map(|item| self.classify(&item.name)).collect();
+        let mut snapshots = vec![items.clone()];
+        for _ in 0..days {
+            for (index, (item, &classification)) in items.iter_mut().zip(&classifications).enumerate() {
+                self.update_item(item, Some(index), classification, None);
+            }
+            snapshots.push(items.clone());
+        }
+        snapshots
+    }
+
+    /// The quality of every item after `days` updates, in order, without changing the shop.
+    /// The same as the qualities in the last snapshot of `simulate`, without keeping the others.
+    pub fn quality_after(&self, days: u32) -> Vec<i32> {
+        let mut items = self.items.clone();
+        let classifications: Vec<Classification> = items.iter().map(|item| self.classify(&item.name)).collect();
+        for _ in 0..days {
+            for (index, (item, &classification)) in items.iter_mut().zip(&classifications).enumerate() {
+                self.update_item(item, Some(index), classification, None);
+            }
+        }
+        items.into_iter().map(|item| item.quality).collect()
+    }
+
+    fn sell_ins(&self, include_legendary: bool) -> impl Iterator<Item = i32> + '_ {
+        self.items.iter()
+            .filter(move |item| include_legendary || self.kind_of(&item.name) != ItemKind::Legendary)
+            .map(|item| item.sell_in)
+    }
+
+    fn states(&self) -> Vec<(i32, i32)> {
+        self.items.iter().map(|item| (item.sell_in, item.quality)).collect()
+    }
+
+    /// Pairs the current items with their `(sell_in, quality)` before the update, passing each
+    /// change to the `on_update` callback.
+    fn changes_since(&mut self, before: Vec<(i32, i32)>) -> Vec<ItemChange> {
+        let changes: Vec<ItemChange> = self.items.iter().zip(before)
+            .map(|(item, (sell_in_before, quality_before))| ItemChange {
+                name: item.name.clone(),
+                sell_in_before,
+                sell_in_after: item.sell_in,
+                quality_before,
+                quality_after: item.quality,
+            })
+            .collect();
+
+        if let Some(callback) = self.on_update.as_mut() {
+            for change in &changes {
+                callback(change);
+            }
+        }
+        changes
+    }
+
+    /// Gives the items that have no id yet the next ones, dropping the ids and strategy overrides
+    /// of items that were taken off the end of `items` directly.
+    fn assign_ids(&mut self) {
+        for id in self.ids.drain(self.items.len().min(self.ids.len())..) {
+            self.overrides.remove(&id);
+        }
+        while self.ids.len() < self.items.len() {
+            self.ids.push(self.next_id);
+            self.next_id += 1;
+        }
+    }
+
+    /// Keeps the items whose flag in `kept` is set, along with their ids and cached
+    /// classifications.
+    fn retain_flagged(&mut self, kept: &[bool]) {
+        self.assign_ids();
+        let mut item_flags = kept.iter();
+        self.items.retain(|_| *item_flags.next().unwrap());
+        self.retain_ids(kept);
+        let mut classification_flags = kept.iter();
+        self.classifications.retain(|_| classification_flags.next().cloned().unwrap_or(false));
+    }
+
+    /// Keeps the ids whose flag in `kept` is set, dropping the strategy overrides of the others.
+    fn retain_ids(&mut self, kept: &[bool]) {
+        let overrides = &mut self.overrides;
+        let mut flags = kept.iter();
+        self.ids.retain(|id| {
+            let keep = *flags.next().unwrap();
+            if !keep {
+                overrides.remove(id);
+            }
+            keep
+        });
+    }
+
+    /// Classifies the items that were added or renamed since they were last classified, and
+    /// gives the added ones their ids.
+    fn refresh_classifications(&mut self) {
+        self.assign_ids();
+        self.classifications.truncate(self.items.len());
+        for index in 0..self.items.len() {
+            let name = &self.items[index].name;
+            match self.classifications.get(index) {
+                Some((classified_name, _)) if classified_name == name => {},
+                Some(_) => self.classifications[index] = (name.clone(), self.classify(name)),
+                None => self.classifications.push((name.clone(), self.classify(name))),
+            }
+        }
+    }
+
+    /// Runs the daily update on the items, counting them in `stats` when given, and passes the
+    /// changes to the `on_update` callback.
+    fn advance(&mut self, stats: Option<&mut UpdateStats>) -> Progress {
+        let before = self.on_update.as_ref().map(|_| self.states());
+        self.refresh_classifications();
+        let mut items = mem::take(&mut self.items);
+        let progress = self.update_classified_items(&mut items, stats);
+        self.items = items;
+        if let Some(before) = before {
+            self.changes_since(before);
+        }
+        progress
+    }
+
+    /// Counts down `days` times the `sell_in` of the items whose next update is `Settled`, the
+    /// way that many updates would. Other items are left alone, so this must only be called when
+    /// the next update changes nothing else.
+    fn count_down_settled_sell_ins(&mut self, days: u32) {
+        self.refresh_classifications();
+        for index in 0..self.items.len() {
+            let classification = self.classifications[index].1;
+            let item = &self.items[index];
+            let (state, _) = self.classified_state(item, Some(index), classification, false);
+            if self.progress(item, Some(index), classification, state) != Progress::Settled {
+                continue;
+            }
+            let sell_in = (i64::from(item.sell_in) - i64::from(days)).max(i64::from(i32::MIN)) as i32;
+            self.items[index].sell_in = match self.sell_in_floor {
+                Some(floor) => sell_in.max(floor.min(item.sell_in)),
+                None => sell_in,
+            };
+        }
+    }
+
+    /// Updates `items` using the cached classifications, which must be up to date for them,
+    /// returning how far the furthest moved.
+    fn update_classified_items(&self, items: &mut [Item], mut stats: Option<&mut UpdateStats>) -> Progress {
+        let mut progress = Progress::Unchanged;
+        for (index, (item, &(_, classification))) in items.iter_mut().zip(&self.classifications).enumerate() {
+            progress = progress.max(self.update_item(item, Some(index), classification, stats.as_deref_mut()));
+        }
+        progress
+    }
+
+    /// `index` is where the item is in this shop, if it is one of its items, for looking up a
+    /// strategy override. The item is counted in `stats` when given.
+    fn update_item(
+        &self, item: &mut Item, index: Option<usize>, classification: Classification, stats: Option<&mut UpdateStats>,
+    ) -> Progress {
+        let ((sell_in, quality), clamping) = self.classified_state(item, index, classification, stats.is_some());
+        if let Some(stats) = stats {
+            match clamping {
+                Ordering::Greater => stats.clamped_to_max += 1,
+                Ordering::Less => stats.clamped_to_min += 1,
+                Ordering::Equal => {},
+            }
+            if !item.is_expired() && sell_in < 0 {
+                stats.expired_this_tick += 1;
+            }
+        }
+        let progress = self.progress(item, index, classification, (sell_in, quality));
+        item.sell_in = sell_in;
+        item.quality = quality;
+        progress
+    }
+
+    /// How far an update moving `item` to `next_state` takes it.
+    fn progress(
+        &self, item: &Item, index: Option<usize>, classification: Classification, next_state: (i32, i32),
+    ) -> Progress {
+        let (sell_in, quality) = next_state;
+        if (sell_in, quality) == (item.sell_in, item.quality) {
+            Progress::Unchanged
+        } else if quality == item.quality
+            && item.sell_in <= self.expiry_threshold.min(0)
+            && index.is_some_and(|index| self.follows_builtin_strategy(index, classification))
+        {
+            Progress::Settled
+        } else {
+            Progress::Changed
+        }
+    }
+
+    /// Whether the item at `index` follows the built-in strategy for its name with nothing
+    /// adjusting it. Built-in strategies no longer depend on `sell_in` once it is at or below
+    /// both 0 and the expiry threshold, so an item that keeps its quality there keeps it for
+    /// good.
+    fn follows_builtin_strategy(&self, index: usize, classification: Classification) -> bool {
+        self.middleware.is_none()
+            && classification.strategy.is_none()
+            && (classification.kind != ItemKind::Conjured || classification.base_strategy.is_none())
+            && !self.overrides.contains_key(&self.ids[index])
+    }
+
+    /// The next `(sell_in, quality)` of `item`, along with how the quality its strategy worked
+    /// out without the bounds compares to the one within them: `Greater` when the maximum
+    /// limited it and `Less` when the minimum did. That is only worked out when `track_clamping`
+    /// is set or the clamp policy needs it, and is `Equal` otherwise.
+    fn classified_state(
+        &self, item: &Item, index: Option<usize>, classification: Classification, track_clamping: bool,
+    ) -> ((i32, i32), Ordering) {
+        let max = self.max_quality_for(classification.base_kind);
+        let (sell_in, quality) = self.strategy_state_within(item, index, classification, self.min_quality, max);
+        let clamping = if track_clamping || self.clamp_policy != ClampPolicy::Saturate {
+            self.strategy_state_within(item, index, classification, i32::MIN, i32::MAX).1.cmp(&quality)
+        } else {
+            Ordering::Equal
+        };
+        let quality = match self.clamp_policy {
+            _ if clamping == Ordering::Equal => quality,
+            ClampPolicy::Saturate => quality,
+            ClampPolicy::Reject => item.quality,
+            ClampPolicy::Reset(reset_quality) => reset_quality,
+        };
+        let sell_in = match self.sell_in_floor {
+            Some(floor) => sell_in.max(floor.min(item.sell_in)),
+            None => sell_in,
+        };
+        let state = match self.middleware.as_ref() {
+            Some(middleware) if !item.frozen && classification.kind != ItemKind::Legendary => {
+                let (quality, sell_in) = middleware(item, quality, sell_in);
+                (sell_in, quality)
+            },
+            _ => (sell_in, quality),
+        };
+        (state, clamping)
+    }
+
+    fn strategy_state_within(
+        &self, item: &Item, index: Option<usize>, classification: Classification, min: i32, max: i32,
+    ) -> (i32, i32) {
+        let override_strategy = index.and_then(|index| self.ids.get(index)).and_then(|id| self.overrides.get(id));
+        let strategy = match override_strategy {
+            Some(strategy) => ResolvedStrategy::Plain(strategy.as_ref()),
+            None => self.classified_strategy(classification),
+        };
+        frozen_or_next_state(item, &strategy, min, max, self.expiry_threshold)
+    }
+
+    /// The strategy for items classified as `classification`: the registration for their name,
+    /// or the built-in behavior of their kind with conjured items following the strategy for
+    /// their base name.
+    fn classified_strategy(&self, classification: Classification) -> ResolvedStrategy<'_> {
+        if let Some(index) = classification.strategy {
+            return ResolvedStrategy::Plain(self.strategies[index].as_ref());
+        }
+        match classification.kind {
+            ItemKind::Conjured => {
+                let base = match classification.base_strategy {
+                    Some(index) => self.strategies[index].as_ref(),
+                    None => classification.base_kind.strategy(),
+                };
+                ResolvedStrategy::Conjured(ConjuredStrategy::with_multiplier(base, self.conjured_multiplier))
+            },
+            kind => ResolvedStrategy::builtin(kind, classification.base_kind),
+        }
+    }
+
+    /// The maximum quality for items whose name without any conjured prefix is of `base_kind`,
+    /// which depends on whether they appreciate.
+    fn max_quality_for(&self, base_kind: ItemKind) -> i32 {
+        match (base_kind, self.appreciating_max_quality) {
+            (ItemKind::AgedBrie, Some(max)) | (ItemKind::BackstagePass, Some(max)) => max,
+            _ => self.max_quality,
+        }
+    }
+
+    fn classify(&self, name: &str) -> Classification {
+        let base_name = self.base_name_of(name);
+        Classification {
+            kind: self.kind_of(name),
+            base_kind: self.kind_of(&base_name),
+            strategy: self.strategy_indices.get(name).cloned(),
+            base_strategy: self.strategy_indices.get(base_name.as_ref()).cloned(),
+        }
+    }
+
+    fn kind_of(&self, name: &str) -> ItemKind {
+        self.names.kind_of(&resolve_name(name), self.case_insensitive_names)
+    }
+
+    /// Strips the conjured prefix from `name`, if it has one.
+    fn base_name_of<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match resolve_name(name) {
+            Cow::Borrowed(name) => Cow::Borrowed(self.names.base_name(name, self.case_insensitive_names).0),
+            Cow::Owned(name) => Cow::Owned(self.names.base_name(&name, self.case_insensitive_names).0.to_string()),
+        }
+    }
+}
+
+/// An empty shop with the default quality bounds of 0 and 50.
+impl Default for GildedRose {
+    fn default() -> GildedRose {
+        GildedRose::new(Vec::new())
+    }
+}
+
+impl Display for GildedRose {
+    /// Writes one item per line, without a trailing newline.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for GildedRose {
+    type Item = Item;
+    type IntoIter = vec::IntoIter<Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a GildedRose {
+    type Item = &'a Item;
+    type IntoIter = slice::Iter<'a, Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut GildedRose {
+    type Item = &'a mut Item;
+    type IntoIter = slice::IterMut<'a, Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
+
+/// Shops are equal when they have equal items and settings. Registered strategies, strategy
+/// overrides, the `on_update` callback and the middleware cannot be compared and are ignored.
+impl PartialEq for GildedRose {
+    fn eq(&self, other: &GildedRose) -> bool {
+        self.items == other.items
+            && self.min_quality == other.min_quality
+            && self.max_quality == other.max_quality
+            && self.names == other.names
+            && self.case_insensitive_names == other.case_insensitive_names
+            && self.sell_in_floor == other.sell_in_floor
+            && self.appreciating_max_quality == other.appreciating_max_quality
+            && self.expiry_threshold == other.expiry_threshold
+            && self.conjured_multiplier == other.conjured_multiplier
+            && self.clamp_policy == other.clamp_policy
+    }
+}
+
+/// Shows the items and settings, leaving out registered strategies, strategy overrides, the
+/// `on_update` callback and the middleware.
+impl fmt::Debug for GildedRose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GildedRose")
+            .field("items", &self.items)
+            .field("min_quality", &self.min_quality)
+            .field("max_quality", &self.max_quality)
+            .field("names", &self.names)
+            .field("case_insensitive_names", &self.case_insensitive_names)
+            .field("sell_in_floor", &self.sell_in_floor)
+            .field("appreciating_max_quality", &self.appreciating_max_quality)
+            .field("expiry_threshold", &self.expiry_threshold)
+            .field("conjured_multiplier", &self.conjured_multiplier)
+            .field("clamp_policy", &self.clamp_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Indexes the items like a slice, panicking when `index` is out of range. Use `get` for a
+/// non-panicking lookup.
+impl Index<usize> for GildedRose {
+    type Output = Item;
+
+    fn index(&self, index: usize) -> &Item {
+        &self.items[index]
+    }
+}
+
+impl FromIterator<Item> for GildedRose {
+    fn from_iter<I: IntoIterator<Item = Item>>(items: I) -> GildedRose {
+        GildedRose::new(items.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl GildedRose {
+    /// Runs the daily update like `update_quality`, spreading the items over rayon's thread pool.
+    /// The `on_update` callback is still called on this thread, after the items are updated.
+    pub fn update_quality_parallel(&mut self) {
+        let before = self.on_update.as_ref().map(|_| self.states());
+        self.refresh_classifications();
+        let callback = self.on_update.take();
+        let mut items = mem::take(&mut self.items);
+        let shop = WithoutCallback(self);
+        items.par_iter_mut().zip(&shop.shop().classifications).enumerate()
+            .for_each(|(index, (item, &(_, classification)))| {
+                shop.shop().update_item(item, Some(index), classification, None);
+            });
+        self.items = items;
+        self.on_update = callback;
+        if let Some(before) = before {
+            self.changes_since(before);
+        }
+    }
+}
+
+/// A shop whose `on_update` callback has been taken out, for sharing it between the threads of
+/// a parallel update. The callback is the only part of a shop that may not be `Sync`.
+#[cfg(feature = "rayon")]
+struct WithoutCallback<'a>(&'a GildedRose);
+
+#[cfg(feature = "rayon")]
+impl<'a> WithoutCallback<'a> {
+    fn shop(&self) -> &GildedRose {
+        self.0
+    }
+}
+
+// SAFETY: with the `rayon` feature every strategy and the middleware are `Send` and `Sync`, and
+// the other fields of a shop are plain data, except for the callback. That is `None` while the
+// shop is wrapped, and could not be called through a shared reference anyway.
+#[cfg(feature = "rayon")]
+unsafe impl<'a> Sync for WithoutCallback<'a> {}
+
+#[cfg(feature = "serde")]
+impl Item {
+    /// A JSON Schema for the objects `GildedRose::from_json` reads, for checking inventories
+    /// before sending them. `degrade_rate` and `frozen` are optional.
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Item",
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "sell_in": { "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX },
+                "quality": { "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX },
+                "degrade_rate": { "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX },
+                "frozen": { "type": "boolean" },
+            },
+            "required": ["name", "sell_in", "quality"],
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl GildedRose {
+    /// Loads a shop from a JSON array of `{ "name", "sell_in", "quality" }` objects.
+    pub fn from_json(json: &str) -> Result<GildedRose, serde_json::Error> {
+        serde_json::from_str(json).map(GildedRose::new)
+    }
+
+    /// Writes the items as a JSON array in the format read by `from_json`.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.items).expect("items always serialize to JSON")
+    }
+
+    /// Loads a shop from JSON Lines, one `{ "name", "sell_in", "quality" }` object per line.
+    /// Blank lines are skipped.
+    pub fn from_ndjson(reader: impl BufRead) -> Result<GildedRose, NdjsonError> {
+        let mut items = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let item = serde_json::from_str(&line)
+                .map_err(|error| NdjsonError::Parse { line: index + 1, error })?;
+            items.push(item);
+        }
+        Ok(GildedRose::new(items))
+    }
+}
+
+/// Why a JSON Lines inventory could not be loaded. Line numbers start at 1.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum NdjsonError {
+    Io(io::Error),
+    Parse { line: usize, error: serde_json::Error },
+}
+
+#[cfg(feature = "serde")]
+impl Display for NdjsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NdjsonError::Io(error) => write!(f, "could not read JSON lines: {}", error),
+            NdjsonError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for NdjsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NdjsonError::Io(error) => Some(error),
+            NdjsonError::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<io::Error> for NdjsonError {
+    fn from(error: io::Error) -> NdjsonError {
+        NdjsonError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClampPolicy, GildedRose, Item, ItemChange, ItemDiff, ItemError, ItemParseError, ItemKind};
+    use super::{ItemValidation, NameConfig, PostConcert, UpdateError, UpdateStats, ValidationReason};
+    use super::{Quality, QualityError, Trend, backstage_target, daily_quality_delta, update_item, update_slice};
+    use super::{UpdateStrategy, AgedBrieStrategy, BackstagePassStrategy, DefaultStrategy, LegendaryStrategy};
+    use super::PeakStrategy;
+    use super::ConjuredStrategy;
+    use super::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+
+    mod regression_test_suite {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_common_items() {
+            // GIVEN common items
+            let items = vec![
+                Item::new(COMMON_ITEM, 10, 20),
+                Item::new(COMMON_ITEM, 5, 7),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN name should stay the same and quality should decrease
+            assert_eq!(COMMON_ITEM, rose.items[0].name);
+            assert_eq!(9, rose.items[0].sell_in);
+            assert_eq!(19, rose.items[0].quality);
+
+            assert_eq!(6, rose.items[1].quality);
+
+            // ...and WHEN update quality 4 more times
+            for _ in 1..=4 {
+                rose.update_quality();
+            }
+
+            // THEN quality should continue to degrade
+            assert_eq!(5, rose.items[0].sell_in);
+            assert_eq!(15, rose.items[0].quality);
+
+            assert_eq!(0, rose.items[1].sell_in);
+            assert_eq!(2, rose.items[1].quality);
+
+            // ...and WHEN updating quality again so sell in becomes negative
+            rose.update_quality();
+
+            // THEN sell in should become negative and quality should degrade twice as fast
+            assert_eq!(-1, rose.items[1].sell_in);
+            assert_eq!(0, rose.items[1].quality);
+
+            // ...and WHEN updating quality when quality is 0
+            rose.update_quality();
+
+            // THEN quality should not become negative
+            assert_eq!(-2, rose.items[1].sell_in);
+            assert_eq!(0, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_legendary_items() {
+            // GIVEN legendary items of different sell in
+            let items = vec![
+                Item::new(LEGENDARY_ITEM, 1, 80),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(LEGENDARY_ITEM, -1, 80)
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN nothing should change
+            assert_eq!(LEGENDARY_ITEM, rose.items[0].name);
+            assert_eq!(1, rose.items[0].sell_in);
+            assert_eq!(80, rose.items[0].quality);
+
+            assert_eq!(0, rose.items[1].sell_in);
+            assert_eq!(80, rose.items[1].quality);
+
+            assert_eq!(-1, rose.items[2].sell_in);
+            assert_eq!(80, rose.items[2].quality);
+
+            // ...and WHEN updating the quality many times
+            for _ in 1..=999 {
+                rose.update_quality();
+            }
+
+            // THEN nothing should change
+            assert_eq!(1, rose.items[0].sell_in);
+            assert_eq!(80, rose.items[0].quality);
+
+            assert_eq!(0, rose.items[1].sell_in);
+            assert_eq!(80, rose.items[1].quality);
+
+            assert_eq!(-1, rose.items[2].sell_in);
+            assert_eq!(80, rose.items[2].quality);
+        }
+
+        #[test]
+        fn test_quality_increasing_items() {
+            // GIVEN items of which quality increases when it gets older
+            let items = vec![
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(AGED_BRIE_ITEM, 4, 49),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should increase
+            assert_eq!(1, rose.items[0].sell_in);
+            assert_eq!(1, rose.items[0].quality);
+
+            assert_eq!(50, rose.items[1].quality);
+
+            // ...and WHEN updating quality again
+            rose.update_quality();
+
+            // THEN quality should increase but be limited by 50
+            assert_eq!(0, rose.items[0].sell_in);
+            assert_eq!(2, rose.items[0].quality);
+
+            assert_eq!(50, rose.items[1].quality);
+
+            // ...and WHEN updating quality again so sell in becomes negative
+            rose.update_quality();
+
+            // THEN quality should increase by 2
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(4, rose.items[0].quality);
+
+            // ...and WHEN updating quality again when sell in is already negative
+            rose.update_quality();
+
+            // THEN quality should continue to increase by 2
+            assert_eq!(6, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_quality_zero_after_sell_in_items() {
+            // GIVEN items of which quality increases more towards sell-in date and becomes 0 after
+            let items = vec![
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+                Item::new(BACKSTAGE_PASSES_ITEM, 10, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 5, 48),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should increase
+            assert_eq!(14, rose.items[0].sell_in);
+            assert_eq!(21, rose.items[0].quality);
+
+            assert_eq!(9, rose.items[1].sell_in);
+            assert_eq!(2, rose.items[1].quality); // increase by 2 when <= 10 days left
+
+            assert_eq!(4, rose.items[2].sell_in);
+            assert_eq!(50, rose.items[2].quality); // not increase past 50
+
+            // ...and WHEN updating quality 4 more times
+            for _ in 1..=4 {
+                rose.update_quality();
+            }
+
+            // THEN quality should increase 4 more times
+            assert_eq!(10, rose.items[0].sell_in);
+            assert_eq!(25, rose.items[0].quality);
+
+            assert_eq!(5, rose.items[1].sell_in);
+            assert_eq!(10, rose.items[1].quality); // increase by 4 x 2 = 8 when <= 10 days left
+
+            assert_eq!(0, rose.items[2].sell_in);
+            assert_eq!(50, rose.items[2].quality); // not increase past 50
+
+            // ...and WHEN updating 1 more time
+            rose.update_quality();
+
+            // THEN quality should become 0 "after the concert"
+            assert_eq!(-1, rose.items[2].sell_in);
+            assert_eq!(0, rose.items[2].quality);
+
+            // ...and WHEN updating 1 more time
+            rose.update_quality();
+
+            // THEN quality should stay 0 "after the concert"
+            assert_eq!(-2, rose.items[2].sell_in);
+            assert_eq!(0, rose.items[2].quality);
+        }
+
+        #[test]
+        fn test_backstage_day_before_and_of_concert() {
+            // GIVEN a backstage pass the day before the concert
+            let mut rose = GildedRose::new(vec![
+                Item::new(BACKSTAGE_PASSES_ITEM, 1, 20),
+            ]);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN it should still gain 3 on its way to the concert day
+            assert_eq!(Item::new(BACKSTAGE_PASSES_ITEM, 0, 23), rose.items[0]);
+
+            // ...and WHEN updating quality on the concert day
+            rose.update_quality();
+
+            // THEN it should be worthless afterwards
+            assert_eq!(Item::new(BACKSTAGE_PASSES_ITEM, -1, 0), rose.items[0]);
+        }
+
+        #[test]
+        fn test_aged_brie_day_before_and_of_expiry() {
+            // GIVEN aged brie the day before its last day of sale
+            let mut rose = GildedRose::new(vec![
+                Item::new(AGED_BRIE_ITEM, 1, 1),
+            ]);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN it should still gain 1
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 0, 2), rose.items[0]);
+
+            // ...and WHEN updating quality on its last day of sale
+            rose.update_quality();
+
+            // THEN it should already gain 2
+            assert_eq!(Item::new(AGED_BRIE_ITEM, -1, 4), rose.items[0]);
+        }
+    }
+
+    mod new_conjured_item_feature {
+        use gildedrose::tests::{CONJURED_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_conjured_item_quality_update() {
+            // GIVEN a conjured item
+            let items = vec![
                 Item::new(CONJURED_ITEM, 3, 18),
             ];
-            let mut rose = GildedRose::new(items);
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should degrade by 2
+            assert_eq!(2, rose.items[0].sell_in);
+            assert_eq!(16, rose.items[0].quality);
+
+            // ...and WHEN updating quality 2 more times
+            for _ in 1..=2 {
+                rose.update_quality();
+            }
+
+            // THEN quality should degrade twice by 2 for a total of 4
+            assert_eq!(0, rose.items[0].sell_in);
+            assert_eq!(12, rose.items[0].quality);
+
+            // ...and WHEN updating quality 1 more time
+            rose.update_quality();
+
+            // THEN quality should degrade twice as fast, so by 4
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(8, rose.items[0].quality);
+        }
+    }
+
+    mod update_strategy_feature {
+        #[cfg(not(feature = "rayon"))]
+        use std::cell::Cell;
+        #[cfg(not(feature = "rayon"))]
+        use std::rc::Rc;
+
+        use super::{GildedRose, Item, UpdateStrategy};
+
+        struct DoublingStrategy;
+
+        impl UpdateStrategy for DoublingStrategy {
+            fn next_state(&self, item: &Item, _min: i32, _max: i32) -> (i32, i32) {
+                (item.sell_in - 1, item.quality * 2)
+            }
+        }
+
+        #[test]
+        fn test_custom_strategy_drives_quality() {
+            // GIVEN an item with a custom strategy registered for its name
+            let items = vec![
+                Item::new("Philosopher's Stone", 5, 3),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Philosopher's Stone", Box::new(DoublingStrategy));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the custom strategy should decide the new quality
+            assert_eq!(4, rose.items[0].sell_in);
+            assert_eq!(6, rose.items[0].quality);
+
+            // ...and WHEN updating quality again
+            rose.update_quality();
+
+            // THEN the custom strategy should keep driving quality
+            assert_eq!(3, rose.items[0].sell_in);
+            assert_eq!(12, rose.items[0].quality);
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        #[test]
+        fn test_strategy_need_not_be_thread_safe() {
+            // GIVEN a strategy counting its calls in a shared cell, which is neither Send nor Sync
+            let calls = Rc::new(Cell::new(0));
+            let counted = Rc::clone(&calls);
+            let mut rose = GildedRose::new(vec![Item::new("Abacus", 5, 3)]);
+            rose.register_strategy("Abacus", Box::new(move |item: &Item| {
+                counted.set(counted.get() + 1);
+                item.quality
+            }));
+
+            // WHEN updating quality twice without the rayon feature
+            rose.update_quality_days(2);
+
+            // THEN the strategy should have been called for both days
+            assert_eq!(2, calls.get());
+        }
+    }
+
+    mod strategy_registry_feature {
+        use gildedrose::tests::{AGED_BRIE_ITEM, COMMON_ITEM};
+        use super::{GildedRose, Item, AgedBrieStrategy, LegendaryStrategy};
+
+        #[test]
+        fn test_register_new_name() {
+            // GIVEN a new item registered to behave like Aged Brie
+            let items = vec![
+                Item::new("Vintage Wine", 1, 10),
+                Item::new(AGED_BRIE_ITEM, 1, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Vintage Wine", Box::new(AgedBrieStrategy::new()));
+
+            // WHEN updating quality twice
+            for _ in 1..=2 {
+                rose.update_quality();
+            }
+
+            // THEN the new item should appreciate exactly like Aged Brie
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(13, rose.items[0].quality);
+            assert_eq!(rose.items[1].quality, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_register_overrides_existing_name() {
+            // GIVEN Aged Brie re-registered to never change
+            let items = vec![
+                Item::new(AGED_BRIE_ITEM, 1, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy(AGED_BRIE_ITEM, Box::new(LegendaryStrategy));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the registration should win over the built-in behavior
+            assert_eq!(10, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_unknown_name_falls_back_to_default() {
+            // GIVEN a shop without any registration for common items
+            let rose = GildedRose::new(vec![]);
+            let item = Item::new(COMMON_ITEM, 5, 10);
+
+            // WHEN looking up the strategy for an unknown name
+            let strategy = rose.strategy_for(COMMON_ITEM);
+
+            // THEN the default degrading strategy should be used
+            assert_eq!((4, 9), strategy.next_state(&item, 0, 50));
+        }
+    }
+
+    mod conjured_prefix_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn conjured(name: &str) -> String {
+            format!("Conjured {}", name)
+        }
+
+        #[test]
+        fn test_conjured_common_item() {
+            // GIVEN a conjured version of a common item
+            let items = vec![
+                Item::new(conjured(COMMON_ITEM), 1, 20),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should degrade by 2
+            assert_eq!(0, rose.items[0].sell_in);
+            assert_eq!(18, rose.items[0].quality);
+
+            // ...and WHEN updating quality again so sell in becomes negative
+            rose.update_quality();
+
+            // THEN quality should degrade by 4
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(14, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_conjured_aged_brie() {
+            // GIVEN a conjured Aged Brie
+            let items = vec![
+                Item::new(conjured(AGED_BRIE_ITEM), 1, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should increase by 2
+            assert_eq!(12, rose.items[0].quality);
+
+            // ...and WHEN updating quality again on the sell in date
+            rose.update_quality();
+
+            // THEN quality should increase by 4
+            assert_eq!(16, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_conjured_backstage_passes() {
+            // GIVEN conjured backstage passes in each tier
+            let items = vec![
+                Item::new(conjured(BACKSTAGE_PASSES_ITEM), 15, 20),
+                Item::new(conjured(BACKSTAGE_PASSES_ITEM), 10, 20),
+                Item::new(conjured(BACKSTAGE_PASSES_ITEM), 5, 20),
+                Item::new(conjured(BACKSTAGE_PASSES_ITEM), 0, 20),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should increase by twice the tier amount, and drop to 0 after the concert
+            assert_eq!(22, rose.items[0].quality);
+            assert_eq!(24, rose.items[1].quality);
+            assert_eq!(26, rose.items[2].quality);
+            assert_eq!(0, rose.items[3].quality);
+        }
+    }
+
+    mod item_derives_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_clone_is_independent_of_update() {
+            // GIVEN an item and a clone of it
+            let item = Item::new(COMMON_ITEM, 10, 20);
+            let snapshot = item.clone();
+            let mut rose = GildedRose::new(vec![item]);
+
+            // WHEN updating quality of the original
+            rose.update_quality();
+
+            // THEN the clone should be untouched
+            assert_eq!(Item::new(COMMON_ITEM, 10, 20), snapshot);
+            assert_ne!(snapshot, rose.items[0]);
+        }
+
+        #[test]
+        fn test_items_from_same_arguments_are_equal() {
+            // GIVEN two items built from the same arguments
+            let first = Item::new(COMMON_ITEM, 10, 20);
+            let second = Item::new(COMMON_ITEM, 10, 20);
+
+            // THEN they should compare equal
+            assert_eq!(first, second);
+        }
+    }
+
+    mod item_kind_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::ItemKind;
+
+        #[test]
+        fn test_known_names_are_classified() {
+            assert_eq!(ItemKind::AgedBrie, ItemKind::from_name(AGED_BRIE_ITEM));
+            assert_eq!(ItemKind::BackstagePass, ItemKind::from_name(BACKSTAGE_PASSES_ITEM));
+            assert_eq!(ItemKind::Conjured, ItemKind::from_name(CONJURED_ITEM));
+            assert_eq!(ItemKind::Legendary, ItemKind::from_name(LEGENDARY_ITEM));
+            assert_eq!(ItemKind::Common, ItemKind::from_name(COMMON_ITEM));
+        }
+
+        #[test]
+        fn test_unknown_name_is_common() {
+            assert_eq!(ItemKind::Common, ItemKind::from_name("+5 Dexterity Vest"));
+            assert_eq!(ItemKind::Common, ItemKind::from_name(""));
+        }
+    }
+
+    mod multi_day_update_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_update_multiple_days() {
+            // GIVEN common items
+            let items = vec![
+                Item::new(COMMON_ITEM, 10, 20),
+                Item::new(COMMON_ITEM, 5, 7),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality for 5 days at once
+            rose.update_quality_days(5);
+
+            // THEN the items should be in the same state as after 5 single updates
+            assert_eq!(5, rose.items[0].sell_in);
+            assert_eq!(15, rose.items[0].quality);
+
+            assert_eq!(0, rose.items[1].sell_in);
+            assert_eq!(2, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_update_zero_days() {
+            // GIVEN a common item
+            let items = vec![
+                Item::new(COMMON_ITEM, 10, 20),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality for 0 days
+            rose.update_quality_days(0);
+
+            // THEN nothing should change
+            assert_eq!(Item::new(COMMON_ITEM, 10, 20), rose.items[0]);
+        }
+    }
+
+    mod configurable_bounds_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, update_item};
+
+        #[test]
+        fn test_raised_maximum_quality() {
+            // GIVEN a shop where quality caps at 100
+            let items = vec![
+                Item::new(AGED_BRIE_ITEM, 10, 49),
+                Item::new(LEGENDARY_ITEM, 10, 80),
+            ];
+            let mut rose = GildedRose::with_bounds(items, 0, 100);
+
+            // WHEN updating quality 5 times
+            rose.update_quality_days(5);
+
+            // THEN Aged Brie should climb past 50 and legendary items should not change
+            assert_eq!(54, rose.items[0].quality);
+            assert_eq!(80, rose.items[1].quality);
+
+            // ...and WHEN updating quality many more times
+            rose.update_quality_days(100);
+
+            // THEN Aged Brie should be limited by the new cap
+            assert_eq!(100, rose.items[0].quality);
+            assert_eq!(80, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_raised_minimum_quality() {
+            // GIVEN a shop where quality never drops below 5
+            let items = vec![
+                Item::new(COMMON_ITEM, 1, 8),
+            ];
+            let mut rose = GildedRose::with_bounds(items, 5, 50);
+
+            // WHEN updating quality 3 times
+            rose.update_quality_days(3);
+
+            // THEN quality should be limited by the new minimum
+            assert_eq!(5, rose.items[0].quality);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_inverted_bounds_are_rejected() {
+            GildedRose::with_bounds(vec![], 50, 0);
+        }
+
+        #[test]
+        fn test_bounds_for_standalone_updates() {
+            // GIVEN a shop where quality caps at 100, and a copy of its item
+            let rose = GildedRose::with_bounds(vec![Item::new(AGED_BRIE_ITEM, 0, 99)], 0, 100);
+            let mut item = rose.items[0].clone();
+
+            // WHEN updating the copy on its own within the shop's bounds
+            update_item(&mut item, rose.min_quality(), rose.max_quality());
+
+            // THEN it should be capped like the shop would
+            assert_eq!((0, 100), (rose.min_quality(), rose.max_quality()));
+            assert_eq!(rose.peek_next(0).unwrap(), item);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod json_feature {
+        use gildedrose::tests::{COMMON_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_json_round_trip() {
+            // GIVEN a shop loaded from JSON
+            let json = r#"[
+                { "name": "Elixir of the Mongoose", "sell_in": 5, "quality": 7 },
+                { "name": "Backstage passes to a TAFKAL80ETC concert", "sell_in": -1, "quality": 0 }
+            ]"#;
+            let rose = GildedRose::from_json(json).unwrap();
+
+            // THEN the items should be parsed
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(BACKSTAGE_PASSES_ITEM, -1, 0),
+            ], rose.items);
+
+            // ...and WHEN serializing and parsing again
+            let reloaded = GildedRose::from_json(&rose.to_json()).unwrap();
+
+            // THEN the items should be equivalent
+            assert_eq!(rose.items, reloaded.items);
+        }
+
+        #[test]
+        fn test_malformed_json() {
+            assert!(GildedRose::from_json("[{ \"name\": \"Aged Brie\" ").is_err());
+            assert!(GildedRose::from_json("[{ \"name\": \"Aged Brie\", \"sell_in\": 2 }]").is_err());
+        }
+
+        #[test]
+        fn test_json_schema() {
+            // GIVEN the schema of an item
+            let schema = Item::json_schema();
+
+            // THEN it should describe the three required fields with their types
+            let properties = &schema["properties"];
+            assert_eq!("string", properties["name"]["type"]);
+            assert_eq!("integer", properties["sell_in"]["type"]);
+            assert_eq!("integer", properties["quality"]["type"]);
+            assert_eq!(i32::MAX, properties["quality"]["maximum"]);
+            assert_eq!(serde_json::json!(["name", "sell_in", "quality"]), schema["required"]);
+        }
+
+        #[test]
+        fn test_json_degrade_rate() {
+            // GIVEN an item with a degrade rate, written as JSON
+            let rose = GildedRose::new(vec![
+                Item { degrade_rate: Some(3), ..Item::new(COMMON_ITEM, 5, 7) },
+            ]);
+            let json = rose.to_json();
+
+            // THEN the rate should be written and read back
+            assert!(json.contains(r#""degrade_rate":3"#));
+            assert_eq!(rose.items, GildedRose::from_json(&json).unwrap().items);
+        }
+    }
+
+    mod update_report_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemChange};
+
+        fn change(name: &str, sell_in: (i32, i32), quality: (i32, i32)) -> ItemChange {
+            ItemChange {
+                name: name.to_string(),
+                sell_in_before: sell_in.0,
+                sell_in_after: sell_in.1,
+                quality_before: quality.0,
+                quality_after: quality.1,
+            }
+        }
+
+        #[test]
+        fn test_update_report() {
+            // GIVEN a mix of common, quality increasing and legendary items
+            let items = vec![
+                Item::new(COMMON_ITEM, 0, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(LEGENDARY_ITEM, -1, 80),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality with a report
+            let report = rose.update_quality_reporting();
+
+            // THEN every item should be reported in order with its exact change
+            assert_eq!(vec![
+                change(COMMON_ITEM, (0, -1), (7, 5)),
+                change(AGED_BRIE_ITEM, (2, 1), (0, 1)),
+                change(LEGENDARY_ITEM, (-1, -1), (80, 80)),
+            ], report);
+
+            // ...and the items should have been updated
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(5, rose.items[0].quality);
+        }
+    }
+
+    mod item_builder_feature {
+        use gildedrose::tests::{AGED_BRIE_ITEM, LEGENDARY_ITEM};
+        use super::{Item, ItemError};
+
+        #[test]
+        fn test_build_valid_item() {
+            let item = Item::builder().name(AGED_BRIE_ITEM).sell_in(5).quality(20).build();
+
+            assert_eq!(Ok(Item::new(AGED_BRIE_ITEM, 5, 20)), item);
+        }
+
+        #[test]
+        fn test_reject_quality_out_of_range() {
+            let over_cap = Item::builder().name(AGED_BRIE_ITEM).sell_in(5).quality(51).build();
+            let negative = Item::builder().name(AGED_BRIE_ITEM).sell_in(5).quality(-1).build();
+
+            assert_eq!(Err(ItemError::QualityOutOfRange { quality: 51 }), over_cap);
+            assert_eq!(Err(ItemError::QualityOutOfRange { quality: -1 }), negative);
+        }
+
+        #[test]
+        fn test_legendary_quality_must_be_80() {
+            let legendary = Item::builder().name(LEGENDARY_ITEM).quality(80).build();
+            let wrong_quality = Item::builder().name(LEGENDARY_ITEM).quality(50).build();
+
+            assert_eq!(Ok(Item::new(LEGENDARY_ITEM, 0, 80)), legendary);
+            assert_eq!(Err(ItemError::LegendaryQuality { quality: 50 }), wrong_quality);
+        }
+
+        #[test]
+        fn test_legendary_name_with_whitespace() {
+            // GIVEN a legendary name with stray whitespace, which the shop treats as legendary
+            let name = format!(" {} ", LEGENDARY_ITEM);
+
+            // WHEN building it with the legendary quality
+            let legendary = Item::builder().name(name.as_str()).quality(80).build();
+
+            // THEN it should be accepted, keeping its name as given
+            assert_eq!(Ok(Item::new(name, 0, 80)), legendary);
+        }
+
+        #[test]
+        fn test_reject_missing_name() {
+            assert_eq!(Err(ItemError::MissingName), Item::builder().sell_in(5).quality(20).build());
+        }
+    }
+
+    mod iterator_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_collect_filtered_inventory() {
+            // GIVEN an inventory with a legendary item
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+
+            // WHEN collecting the non-legendary items into a new inventory
+            let mut perishables: GildedRose = rose.into_iter()
+                .filter(|item| item.name != LEGENDARY_ITEM)
+                .collect();
+            perishables.update_quality();
+
+            // THEN the new inventory should hold and update the remaining items
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 4, 6),
+                Item::new(AGED_BRIE_ITEM, 1, 1),
+            ], perishables.items);
+        }
+
+        #[test]
+        fn test_loop_over_inventory() {
+            // GIVEN an inventory
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+
+            // WHEN looping over mutable references
+            for item in &mut rose {
+                item.sell_in += 10;
+            }
+
+            // THEN a loop over references should see every item in order
+            let mut seen = vec![];
+            for item in &rose {
+                seen.push((item.name.as_str(), item.sell_in));
+            }
+            assert_eq!(vec![(COMMON_ITEM, 15), (AGED_BRIE_ITEM, 12)], seen);
+        }
+    }
+
+    mod find_items_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(COMMON_ITEM, 10, 20),
+            ])
+        }
+
+        #[test]
+        fn test_find_item() {
+            let mut rose = inventory();
+
+            assert_eq!(Some(&Item::new(AGED_BRIE_ITEM, 2, 0)), rose.find(AGED_BRIE_ITEM));
+            assert_eq!(Some(&Item::new(COMMON_ITEM, 5, 7)), rose.find(COMMON_ITEM));
+
+            rose.find_mut(AGED_BRIE_ITEM).unwrap().quality = 10;
+            assert_eq!(10, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_find_missing_item() {
+            let mut rose = inventory();
+
+            assert_eq!(None, rose.find(LEGENDARY_ITEM));
+            assert_eq!(None, rose.find_mut(LEGENDARY_ITEM));
+            assert!(rose.items_with_name(LEGENDARY_ITEM).is_empty());
+        }
+
+        #[test]
+        fn test_items_sharing_a_name() {
+            let rose = inventory();
+
+            assert_eq!(vec![
+                &Item::new(COMMON_ITEM, 5, 7),
+                &Item::new(COMMON_ITEM, 10, 20),
+            ], rose.items_with_name(COMMON_ITEM));
+        }
+    }
+
+    mod legendary_strategy_feature {
+        use gildedrose::tests::{LEGENDARY_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_legendary_item_with_own_quality() {
+            // GIVEN a legendary item recorded at a quality other than 80
+            let items = vec![
+                Item::new(LEGENDARY_ITEM, 3, 50),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality many times
+            rose.update_quality_days(999);
+
+            // THEN nothing should change
+            assert_eq!(Item::new(LEGENDARY_ITEM, 3, 50), rose.items[0]);
+        }
+
+        #[test]
+        fn test_legendary_item_outside_bounds() {
+            // GIVEN a legendary item in a shop that caps quality far below it
+            let items = vec![
+                Item::new(LEGENDARY_ITEM, 3, 80),
+            ];
+            let mut rose = GildedRose::with_bounds(items, 0, 10);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the legendary item should not be clamped
+            assert_eq!(Item::new(LEGENDARY_ITEM, 3, 80), rose.items[0]);
+        }
+    }
+
+    mod legendary_sell_in_regression {
+        use gildedrose::tests::{LEGENDARY_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_legendary_sell_in_never_moves() {
+            // GIVEN legendary items of different sell in
+            let items = vec![
+                Item::new(LEGENDARY_ITEM, 5, 80),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(LEGENDARY_ITEM, -5, 80),
+            ];
+            let mut rose = GildedRose::new(items);
+
+            // WHEN updating quality 1000 times
+            for _ in 1..=1000 {
+                rose.update_quality();
+
+                // THEN sell in should never move
+                assert_eq!(5, rose.items[0].sell_in);
+                assert_eq!(0, rose.items[1].sell_in);
+                assert_eq!(-5, rose.items[2].sell_in);
+            }
+        }
+    }
+
+    mod strategy_state_feature {
+        use super::{GildedRose, Item, UpdateStrategy, LegendaryStrategy};
+
+        struct FastForwardStrategy;
+
+        impl UpdateStrategy for FastForwardStrategy {
+            fn next_state(&self, item: &Item, _min: i32, _max: i32) -> (i32, i32) {
+                (item.sell_in - 7, item.quality)
+            }
+        }
+
+        #[test]
+        fn test_strategy_owns_sell_in() {
+            // GIVEN an item that ages a week per day and a new name registered as legendary
+            let items = vec![
+                Item::new("Hourglass of Haste", 10, 20),
+                Item::new("Excalibur", 10, 80),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Hourglass of Haste", Box::new(FastForwardStrategy));
+            rose.register_strategy("Excalibur", Box::new(LegendaryStrategy));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN sell in should follow the strategies
+            assert_eq!(Item::new("Hourglass of Haste", 3, 20), rose.items[0]);
+            assert_eq!(Item::new("Excalibur", 10, 80), rose.items[1]);
+        }
+    }
+
+    mod conjured_doubling_feature {
+        use super::{GildedRose, Item, AgedBrieStrategy, ConjuredStrategy, DefaultStrategy};
+
+        #[test]
+        fn test_doubling_default_strategy() {
+            // GIVEN an item doubling the default strategy
+            let items = vec![
+                Item::new("Conjured Elixir", 3, 18),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Conjured Elixir", Box::new(ConjuredStrategy::new(DefaultStrategy::new())));
+
+            // WHEN updating quality 3 times
+            rose.update_quality_days(3);
+
+            // THEN quality should degrade by 2 each day
+            assert_eq!(0, rose.items[0].sell_in);
+            assert_eq!(12, rose.items[0].quality);
+
+            // ...and WHEN updating quality 1 more time
+            rose.update_quality();
+
+            // THEN quality should degrade twice as fast, so by 4
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(8, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_doubling_gains() {
+            // GIVEN a cheese doubling Aged Brie, next to conjured Aged Brie going by its name
+            let items = vec![
+                Item::new("Conjured Cheddar", 5, 10),
+                Item::new("Conjured Aged Brie", 5, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Conjured Cheddar", Box::new(ConjuredStrategy::new(AgedBrieStrategy::new())));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN both should gain twice as fast
+            assert_eq!(12, rose.items[0].quality);
+            assert_eq!(12, rose.items[1].quality);
+        }
+    }
+
+    mod validation_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, ItemValidation, ValidationReason, AgedBrieStrategy};
+
+        #[test]
+        fn test_clean_inventory() {
+            // GIVEN a clean inventory
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, -3, 50),
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 0),
+                Item::new(CONJURED_ITEM, 3, 6),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ]);
+
+            // THEN validation should find nothing
+            assert!(rose.validate().is_empty());
+        }
+
+        #[test]
+        fn test_dirty_inventory() {
+            // GIVEN an inventory with problems at indices 1, 2, 4 and 5
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(COMMON_ITEM, 5, 80),
+                Item::new(LEGENDARY_ITEM, -1, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new("aged brie", 2, -1),
+                Item::new("conjured mana cake", 3, 6),
+            ]);
+
+            // WHEN validating the inventory
+            let validations = rose.validate();
+
+            // THEN every problem should be reported with its index, and nothing should change
+            assert_eq!(vec![
+                ItemValidation { index: 1, reason: ValidationReason::QualityOutOfRange { quality: 80 } },
+                ItemValidation { index: 2, reason: ValidationReason::LegendaryNegativeSellIn { sell_in: -1 } },
+                ItemValidation { index: 4, reason: ValidationReason::QualityOutOfRange { quality: -1 } },
+                ItemValidation { index: 4, reason: ValidationReason::UnknownName { resembles: AGED_BRIE_ITEM } },
+                ItemValidation { index: 5, reason: ValidationReason::UnknownName { resembles: "Conjured " } },
+            ], validations);
+            assert_eq!(80, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_registered_name_is_known() {
+            // GIVEN a look-alike name that has its own registration
+            let mut rose = GildedRose::new(vec![
+                Item::new("aged brie", 2, 0),
+            ]);
+            rose.register_strategy("aged brie", Box::new(AgedBrieStrategy::new()));
+
+            // THEN it should not be reported
+            assert!(rose.validate().is_empty());
+        }
+    }
+
+    mod simulation_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> Vec<Item> {
+            vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(BACKSTAGE_PASSES_ITEM, 3, 45),
+            ]
+        }
+
+        #[test]
+        fn test_simulate_matches_updates() {
+            // GIVEN two identical shops
+            let rose = GildedRose::new(inventory());
+            let mut updated = GildedRose::new(inventory());
+
+            // WHEN simulating 10 days on one and updating the other 10 times
+            let snapshots = rose.simulate(10);
+            updated.update_quality_days(10);
+
+            // THEN there should be a snapshot per day plus the start, ending in the updated state
+            assert_eq!(11, snapshots.len());
+            assert_eq!(inventory(), snapshots[0]);
+            assert_eq!(updated.items, snapshots[10]);
+
+            // ...and the simulated shop should not have changed
+            assert_eq!(inventory(), rose.items);
+        }
+
+        #[test]
+        fn test_simulate_zero_days() {
+            let rose = GildedRose::new(inventory());
+
+            assert_eq!(vec![inventory()], rose.simulate(0));
+        }
+
+        #[test]
+        fn test_quality_after_matches_simulation() {
+            // GIVEN a shop
+            let rose = GildedRose::new(inventory());
+
+            // WHEN asking for the qualities after 10 days
+            let qualities = rose.quality_after(10);
+
+            // THEN they should be those of the last simulated snapshot
+            let last = rose.simulate(10).pop().unwrap();
+            assert_eq!(last.iter().map(|item| item.quality).collect::<Vec<_>>(), qualities);
+
+            // ...and the shop should not have changed
+            assert_eq!(inventory(), rose.items);
+            assert_eq!(vec![7, 0, 80, 45], rose.quality_after(0));
+        }
+    }
+
+    mod quality_totals_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_mixed_inventory_totals() {
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(COMMON_ITEM, 10, 21),
+            ]);
+
+            assert_eq!(108, rose.total_quality());
+            assert_eq!(Some(27.0), rose.average_quality());
+        }
+
+        #[test]
+        fn test_total_does_not_overflow() {
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, i32::MAX),
+                Item::new(COMMON_ITEM, 5, i32::MAX),
+            ]);
+
+            assert_eq!(2 * i64::from(i32::MAX), rose.total_quality());
+        }
+
+        #[test]
+        fn test_empty_inventory_totals() {
+            let rose = GildedRose::new(vec![]);
+
+            assert_eq!(0, rose.total_quality());
+            assert_eq!(None, rose.average_quality());
+        }
+
+        #[test]
+        fn test_single_item_average() {
+            let rose = GildedRose::new(vec![
+                Item::new(AGED_BRIE_ITEM, 2, 13),
+            ]);
+
+            assert_eq!(Some(13.0), rose.average_quality());
+        }
+    }
+
+    mod display_inventory_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_display_inventory() {
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, -2, 0),
+            ]);
+
+            assert_eq!("Elixir of the Mongoose, 5, 7\nAged Brie, -2, 0", rose.to_string());
+        }
+
+        #[test]
+        fn test_display_empty_inventory() {
+            assert_eq!("", GildedRose::new(vec![]).to_string());
+        }
+
+        #[test]
+        fn test_item_with_separator() {
+            // GIVEN an expired item
+            let item = Item::new(AGED_BRIE_ITEM, -2, 20);
+
+            // THEN it should be written with the given separator, the default staying ", "
+            assert_eq!("Aged Brie;-2;20", item.to_string_with_separator(";"));
+            assert_eq!("Aged Brie\t-2\t20", item.to_string_with_separator("\t"));
+            assert_eq!(item.to_string(), item.to_string_with_separator(", "));
+        }
+    }
+
+    mod post_concert_feature {
+        use gildedrose::tests::{BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, PostConcert};
+
+        #[test]
+        fn test_linear_post_concert_loss() {
+            // GIVEN backstage passes that lose 5 quality per day after the concert
+            let items = vec![
+                Item::new(BACKSTAGE_PASSES_ITEM, 1, 20),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.set_backstage_post_concert(PostConcert::Linear(5));
+
+            // WHEN updating quality on the day before the concert
+            rose.update_quality();
+
+            // THEN quality should still increase
+            assert_eq!(0, rose.items[0].sell_in);
+            assert_eq!(23, rose.items[0].quality);
+
+            // ...and WHEN updating quality after the concert
+            rose.update_quality();
+
+            // THEN quality should step down by 5 instead of dropping to 0
+            assert_eq!(-1, rose.items[0].sell_in);
+            assert_eq!(18, rose.items[0].quality);
+
+            // ...and WHEN updating quality 4 more times
+            rose.update_quality_days(4);
+
+            // THEN quality should keep stepping down by 5 without becoming negative
+            assert_eq!(-5, rose.items[0].sell_in);
+            assert_eq!(0, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_zero_post_concert_is_default() {
+            // GIVEN backstage passes explicitly set to drop to 0 after the concert
+            let items = vec![
+                Item::new(BACKSTAGE_PASSES_ITEM, 0, 20),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.set_backstage_post_concert(PostConcert::default());
+
+            // WHEN updating quality after the concert
+            rose.update_quality();
+
+            // THEN quality should drop to 0
+            assert_eq!(0, rose.items[0].quality);
+        }
+    }
+
+    mod backstage_tiers_feature {
+        use super::{GildedRose, Item, BackstagePassStrategy};
+
+        #[test]
+        fn test_custom_backstage_tiers() {
+            // GIVEN passes gaining +4 at 3 days or less, +3 at 7 or less and +2 at 14 or less
+            let items = vec![
+                Item::new("Dragon Concert", 20, 10),
+                Item::new("Dragon Concert", 14, 10),
+                Item::new("Dragon Concert", 8, 10),
+                Item::new("Dragon Concert", 7, 10),
+                Item::new("Dragon Concert", 3, 10),
+                Item::new("Dragon Concert", 0, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+            let tiers = vec![(14, 2), (3, 4), (7, 3)];
+            rose.register_strategy("Dragon Concert", Box::new(BackstagePassStrategy::with_tiers(tiers)));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN each pass should gain by its tier, and drop to 0 after the concert
+            assert_eq!(11, rose.items[0].quality);
+            assert_eq!(12, rose.items[1].quality);
+            assert_eq!(12, rose.items[2].quality);
+            assert_eq!(13, rose.items[3].quality);
+            assert_eq!(14, rose.items[4].quality);
+            assert_eq!(0, rose.items[5].quality);
+        }
+
+        #[test]
+        fn test_default_tiers() {
+            // GIVEN passes with the default tiers in each tier
+            let items = vec![
+                Item::new("Regular Concert", 11, 10),
+                Item::new("Regular Concert", 10, 10),
+                Item::new("Regular Concert", 6, 10),
+                Item::new("Regular Concert", 5, 10),
+                Item::new("Regular Concert", 1, 10),
+            ];
+            let mut rose = GildedRose::new(items);
+            rose.register_strategy("Regular Concert", Box::new(BackstagePassStrategy::new()));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the regular +1/+2/+3 schedule should apply
+            let qualities: Vec<i32> = rose.items.iter().map(|item| item.quality).collect();
+            assert_eq!(vec![11, 12, 12, 13, 13], qualities);
+        }
+    }
+
+    mod inventory_mutators_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_push_then_update() {
+            // GIVEN an empty shop with an item pushed into it
+            let mut rose = GildedRose::new(vec![]);
+            rose.push(Item::new(AGED_BRIE_ITEM, 2, 0));
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the pushed item should be updated
+            assert_eq!(1, rose.len());
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 1, 1), rose.items[0]);
+        }
+
+        #[test]
+        fn test_remove_middle_item() {
+            // GIVEN three items
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ]);
+
+            // WHEN removing the middle item
+            let removed = rose.remove(1);
+
+            // THEN it should be returned and the others should keep their order
+            assert_eq!(Some(Item::new(AGED_BRIE_ITEM, 2, 0)), removed);
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ], rose.items);
+
+            // ...and WHEN removing an index out of range
+            // THEN nothing should be removed
+            assert_eq!(None, rose.remove(2));
+            assert_eq!(2, rose.len());
+        }
+
+        #[test]
+        fn test_clear() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+            ]);
+            assert!(!rose.is_empty());
+
+            rose.clear();
+
+            assert!(rose.is_empty());
+            assert_eq!(0, rose.len());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    mod parallel_update_feature {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, ItemChange};
+
+        fn inventory() -> Vec<Item> {
+            let names = [COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM];
+            (0..10_000).map(|i| Item::new(names[i % names.len()], (i % 30) as i32 - 10, (i % 51) as i32)).collect()
+        }
+
+        #[test]
+        fn test_parallel_update_matches_sequential() {
+            // GIVEN two identical large shops
+            let mut sequential = GildedRose::new(inventory());
+            let mut parallel = GildedRose::new(inventory());
+
+            // WHEN updating one sequentially and the other in parallel for 20 days
+            for _ in 1..=20 {
+                sequential.update_quality();
+                parallel.update_quality_parallel();
+            }
+
+            // THEN both should end in the same state
+            assert_eq!(sequential.items, parallel.items);
+        }
+
+        #[test]
+        fn test_parallel_update_calls_back() {
+            // GIVEN a shop with a callback counting the changes in a cell
+            let mut rose = GildedRose::new(inventory());
+            let count = Rc::new(Cell::new(0));
+            let counter = Rc::clone(&count);
+            rose.on_update(Box::new(move |_: &ItemChange| counter.set(counter.get() + 1)));
+
+            // WHEN updating in parallel twice
+            rose.update_quality_parallel();
+            rose.update_quality_parallel();
+
+            // THEN the callback should have seen every item on both days, and still be set
+            assert_eq!(2 * rose.len(), count.get());
+            assert!(rose.on_update.is_some());
+        }
+    }
+
+    mod capacity_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_with_capacity() {
+            // GIVEN a shop created with room for 100 items
+            let mut rose = GildedRose::with_capacity(100);
+
+            // THEN it should be empty with at least that capacity
+            assert!(rose.is_empty());
+            assert!(rose.capacity() >= 100);
+
+            // ...and WHEN pushing 100 items
+            let capacity = rose.capacity();
+            for _ in 0..100 {
+                rose.push(Item::new(COMMON_ITEM, 5, 7));
+            }
+
+            // THEN it should not have reallocated
+            assert_eq!(100, rose.len());
+            assert_eq!(capacity, rose.capacity());
+        }
+    }
+
+    mod peek_next_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_peek_matches_update() {
+            // GIVEN a shop
+            let items = vec![
+                Item::new(COMMON_ITEM, 0, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 5, 20),
+            ];
+            let mut rose = GildedRose::new(items.clone());
+
+            // WHEN peeking at every item
+            let peeked: Vec<Item> = (0..3).map(|index| rose.peek_next(index).unwrap()).collect();
+
+            // THEN the shop should be untouched
+            assert_eq!(items, rose.items);
+
+            // ...and WHEN actually updating quality
+            rose.update_quality();
+
+            // THEN the peeked items should match the updated ones
+            assert_eq!(rose.items, peeked);
+        }
+
+        #[test]
+        fn test_peek_out_of_range() {
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 0, 7),
+            ]);
+
+            assert_eq!(None, rose.peek_next(1));
+        }
+    }
+
+    mod quality_overflow_feature {
+        use gildedrose::tests::{CONJURED_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_extreme_quality_clamps_without_overflow() {
+            // GIVEN items with pathological qualities
+            let items = vec![
+                Item::new(AGED_BRIE_ITEM, 0, i32::MAX),
+                Item::new(BACKSTAGE_PASSES_ITEM, 3, i32::MAX),
+                Item::new(CONJURED_ITEM, 0, i32::MAX),
+                Item::new(CONJURED_ITEM, 0, i32::MIN),
+            ];
+            let mut rose = GildedRose::with_bounds(items, 0, 100);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN quality should clamp to the configured bounds instead of overflowing
+            assert_eq!(100, rose.items[0].quality);
+            assert_eq!(100, rose.items[1].quality);
+            assert_eq!(100, rose.items[2].quality);
+            assert_eq!(0, rose.items[3].quality);
+        }
+    }
+
+    mod expired_items_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_is_expired_boundary() {
+            assert!(!Item::new(COMMON_ITEM, 1, 7).is_expired());
+            assert!(!Item::new(COMMON_ITEM, 0, 7).is_expired());
+            assert!(Item::new(COMMON_ITEM, -1, 7).is_expired());
+        }
+
+        #[test]
+        fn test_expired_items() {
+            // GIVEN a shop with items around their sell by date
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 1, 7),
+                Item::new(AGED_BRIE_ITEM, -1, 10),
+                Item::new(COMMON_ITEM, 0, 7),
+                Item::new(COMMON_ITEM, -1, 3),
+            ]);
+
+            // WHEN asking for the expired items
+            let expired = rose.expired_items();
+
+            // THEN only the ones with a negative sell_in should be returned, in order
+            assert_eq!(vec![&rose.items[1], &rose.items[3]], expired);
+        }
+    }
+
+    mod quality_newtype_feature {
+        use super::{Quality, QualityError};
+
+        #[test]
+        fn test_valid_quality() {
+            assert_eq!(0, *Quality::new(0).unwrap());
+            assert_eq!(50, *Quality::new(50).unwrap());
+            assert_eq!(7, i32::from(Quality::new(7).unwrap()));
+        }
+
+        #[test]
+        fn test_out_of_range_quality() {
+            assert_eq!(Err(QualityError::OutOfRange { quality: 51 }), Quality::new(51));
+            assert_eq!(Err(QualityError::OutOfRange { quality: -1 }), Quality::new(-1));
+        }
+
+        #[test]
+        fn test_saturating_adjust_at_boundary() {
+            // GIVEN qualities at the bounds
+            let top = Quality::new(50).unwrap();
+            let bottom = Quality::new(0).unwrap();
+
+            // WHEN pushing them past the bounds
+            // THEN they should stay at the bounds
+            assert_eq!(50, *top.saturating_adjust(1, 0, 50));
+            assert_eq!(0, *bottom.saturating_adjust(-2, 0, 50));
+            assert_eq!(50, *top.saturating_adjust(i32::MAX, 0, 50));
+            assert_eq!(49, *top.saturating_adjust(-1, 0, 50));
+        }
+
+        #[test]
+        fn test_saturating_adjust_stays_valid() {
+            // GIVEN a valid quality
+            let quality = Quality::new(40).unwrap();
+
+            // WHEN adjusting it with bounds wider than the allowed range
+            // THEN it should still be kept within 0 to 50
+            assert_eq!(Quality::new(50).unwrap(), quality.saturating_adjust(100, 0, 80));
+            assert_eq!(Quality::new(0).unwrap(), quality.saturating_adjust(-100, -10, 50));
+        }
+
+        #[test]
+        #[should_panic(expected = "minimum quality 10 is greater than maximum quality 5")]
+        fn test_saturating_adjust_inverted_bounds() {
+            Quality::new(7).unwrap().saturating_adjust(1, 10, 5);
+        }
+    }
+
+    mod brie_ceiling_feature {
+        use gildedrose::tests::{AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, AgedBrieStrategy};
+
+        #[test]
+        fn test_quality_climbs_to_custom_ceiling() {
+            // GIVEN a premium cheese that tops out at 60, above the shop's maximum of 50
+            let mut rose = GildedRose::new(vec![
+                Item::new("Premium Cheese", 5, 45),
+                Item::new(AGED_BRIE_ITEM, 5, 45),
+            ]);
+            rose.register_strategy("Premium Cheese", Box::new(AgedBrieStrategy::with_ceiling(60)));
+
+            // WHEN updating quality for well past the time it needs
+            rose.update_quality_days(20);
+
+            // THEN the cheese should stop at its own ceiling and the brie at the shop's maximum
+            assert_eq!(60, rose.items[0].quality);
+            assert_eq!(50, rose.items[1].quality);
+        }
+
+        #[test]
+        fn test_ceiling_below_shop_maximum() {
+            let mut rose = GildedRose::new(vec![
+                Item::new("Young Cheese", 5, 18),
+            ]);
+            rose.register_strategy("Young Cheese", Box::new(AgedBrieStrategy::with_ceiling(20)));
+
+            rose.update_quality_days(5);
+
+            assert_eq!(20, rose.items[0].quality);
+        }
+    }
+
+    mod on_update_feature {
+        use std::cell::{Cell, RefCell};
+        use std::rc::Rc;
+
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemChange};
+
+        #[test]
+        fn test_callback_sees_every_change() {
+            // GIVEN a shop with a callback collecting changes into a shared list
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+            let changes = Rc::new(RefCell::new(Vec::new()));
+            let collected = Rc::clone(&changes);
+            rose.on_update(Box::new(move |change: &ItemChange| collected.borrow_mut().push(change.clone())));
+
+            // WHEN updating quality twice
+            rose.update_quality();
+            rose.update_quality();
+
+            // THEN the callback should have seen both items on both days, in item order
+            let changes = changes.borrow();
+            assert_eq!(4, changes.len());
+            assert_eq!(ItemChange {
+                name: COMMON_ITEM.to_string(),
+                sell_in_before: 5,
+                sell_in_after: 4,
+                quality_before: 7,
+                quality_after: 6,
+            }, changes[0]);
+            assert_eq!(ItemChange {
+                name: AGED_BRIE_ITEM.to_string(),
+                sell_in_before: 1,
+                sell_in_after: 0,
+                quality_before: 1,
+                quality_after: 2,
+            }, changes[3]);
+        }
+
+        #[test]
+        fn test_reporting_also_calls_back() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+            ]);
+            let count = Rc::new(Cell::new(0));
+            let counter = Rc::clone(&count);
+            rose.on_update(Box::new(move |_: &ItemChange| counter.set(counter.get() + 1)));
+
+            let reported = rose.update_quality_reporting();
+
+            assert_eq!(1, reported.len());
+            assert_eq!(1, count.get());
+        }
+    }
+
+    mod ordering_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_sort_mixed_inventory() {
+            // GIVEN a mixed inventory
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 10, 49),
+            ]);
+
+            // WHEN sorting by quality
+            rose.sort_by_quality();
+
+            // THEN the most valuable items should come first
+            assert_eq!(vec![
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(BACKSTAGE_PASSES_ITEM, 10, 49),
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ], rose.items);
+        }
+
+        #[test]
+        fn test_equal_quality_orders_by_sooner_sell_in_then_name() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, -1, 10),
+                Item::new(AGED_BRIE_ITEM, 5, 10),
+            ]);
+
+            rose.sort_by_quality();
+
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, -1, 10),
+                Item::new(AGED_BRIE_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, 5, 10),
+            ], rose.items);
+        }
+    }
+
+    mod diff_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemDiff};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ])
+        }
+
+        #[test]
+        fn test_equal_inventories() {
+            assert_eq!(Vec::<ItemDiff>::new(), inventory().diff(&inventory()));
+        }
+
+        #[test]
+        fn test_changed_item() {
+            // GIVEN a shop and a copy that has been updated
+            let rose = inventory();
+            let mut updated = inventory();
+            updated.update_quality();
+
+            // WHEN diffing them
+            let diff = rose.diff(&updated);
+
+            // THEN both items should report how they moved
+            assert_eq!(vec![
+                ItemDiff::Changed { index: 0, name: COMMON_ITEM.to_string(), sell_in_delta: -1, quality_delta: -1 },
+                ItemDiff::Changed { index: 1, name: AGED_BRIE_ITEM.to_string(), sell_in_delta: -1, quality_delta: 1 },
+            ], diff);
+        }
+
+        #[test]
+        fn test_different_lengths() {
+            // GIVEN a shop with one item more than the other
+            let rose = inventory();
+            let mut longer = inventory();
+            longer.push(Item::new("Mead", 3, 4));
+
+            // WHEN diffing them both ways
+            // THEN the extra item should show up as added or removed
+            assert_eq!(vec![ItemDiff::AddedInOther { index: 2, item: Item::new("Mead", 3, 4) }], rose.diff(&longer));
+            assert_eq!(vec![ItemDiff::RemovedInOther { index: 2, item: Item::new("Mead", 3, 4) }], longer.diff(&rose));
+        }
+
+        #[test]
+        fn test_renamed_item() {
+            let rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 5, 7)]);
+            let other = GildedRose::new(vec![Item::new("Mead", 5, 7)]);
+
+            assert_eq!(vec![
+                ItemDiff::RemovedInOther { index: 0, item: Item::new(COMMON_ITEM, 5, 7) },
+                ItemDiff::AddedInOther { index: 0, item: Item::new("Mead", 5, 7) },
+            ], rose.diff(&other));
+        }
+    }
+
+    mod snapshot_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, LegendaryStrategy};
+
+        #[test]
+        fn test_restore_undoes_updates() {
+            // GIVEN a shop and a snapshot of it
+            let items = vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 5, 49),
+            ];
+            let mut rose = GildedRose::new(items.clone());
+            let snapshot = rose.snapshot();
+
+            // WHEN updating several times and changing the inventory
+            rose.update_quality_days(4);
+            rose.push(Item::new("Mead", 3, 4));
+            assert_ne!(items, rose.items);
+
+            // ...and restoring the snapshot
+            assert_eq!(&items[..], snapshot.items());
+            rose.restore(snapshot);
+
+            // THEN the original items should be back
+            assert_eq!(items, rose.items);
+        }
+
+        #[test]
+        fn test_restore_keeps_ids_and_overrides() {
+            // GIVEN a shop with an override on its last item, and a snapshot of it
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+            rose.apply_strategy_override(1, Box::new(LegendaryStrategy));
+            let ids = vec![rose.id_of(0).unwrap(), rose.id_of(1).unwrap()];
+            let snapshot = rose.snapshot();
+
+            // WHEN removing an item, pushing another and restoring the snapshot
+            rose.remove(0);
+            rose.push(Item::new("Mead", 3, 4));
+            rose.restore(snapshot);
+
+            // THEN the items should have their ids back, and new items should not reuse one
+            assert_eq!(Some(&Item::new(COMMON_ITEM, 5, 7)), rose.find_by_id(ids[0]));
+            assert_eq!(ids, vec![rose.id_of(0).unwrap(), rose.id_of(1).unwrap()]);
+            rose.push(Item::new("Mead", 3, 4));
+            assert_eq!(Some(3), rose.id_of(2));
+
+            // ...and the override should still apply
+            rose.update_quality();
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 2, 0), rose[1]);
+        }
+    }
+
+    mod case_insensitive_names_feature {
+        use super::{GildedRose, Item};
+
+        fn shouting_inventory() -> Vec<Item> {
+            vec![
+                Item::new("AGED BRIE", 5, 10),
+                Item::new("sulfuras, hand of ragnaros", 0, 80),
+                Item::new("CONJURED aged brie", 5, 10),
+            ]
+        }
+
+        #[test]
+        fn test_flag_on_matches_any_case() {
+            // GIVEN a shop matching names case-insensitively
+            let mut rose = GildedRose::new(shouting_inventory()).with_case_insensitive_names(true);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the items should behave like their built-in counterparts
+            assert_eq!(Item::new("AGED BRIE", 4, 11), rose.items[0]);
+            assert_eq!(Item::new("sulfuras, hand of ragnaros", 0, 80), rose.items[1]);
+            assert_eq!(Item::new("CONJURED aged brie", 4, 12), rose.items[2]);
+        }
+
+        #[test]
+        fn test_flag_off_by_default() {
+            // GIVEN a shop with the default exact matching
+            let mut rose = GildedRose::new(shouting_inventory());
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the items should degrade like common items
+            assert_eq!(Item::new("AGED BRIE", 4, 9), rose.items[0]);
+            assert_eq!(Item::new("sulfuras, hand of ragnaros", -1, 50), rose.items[1]);
+            assert_eq!(Item::new("CONJURED aged brie", 4, 9), rose.items[2]);
+        }
+    }
+
+    mod name_whitespace_feature {
+        use gildedrose::tests::{AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemKind};
+
+        #[test]
+        fn test_padded_name_dispatches_but_keeps_spaces() {
+            // GIVEN an imported brie whose name has stray spaces
+            let mut rose = GildedRose::new(vec![
+                Item::new(" Aged Brie ", 5, 10),
+                Item::new(" Conjured Aged Brie", 5, 10),
+            ]);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN it should appreciate like aged brie, keeping its name as given
+            assert_eq!(Item::new(" Aged Brie ", 4, 11), rose.items[0]);
+            assert_eq!(Item::new(" Conjured Aged Brie", 4, 12), rose.items[1]);
+            assert_eq!(" Aged Brie , 4, 11", rose.items[0].to_string());
+            assert!(rose.validate().is_empty());
+        }
+
+        #[test]
+        fn test_classification_trims() {
+            let rose = GildedRose::new(Vec::new());
+
+            assert_eq!(ItemKind::AgedBrie, rose.kind_of(" Aged Brie "));
+            assert_eq!(ItemKind::Common, ItemKind::from_name(" Aged Brie "));
+            assert_eq!(AGED_BRIE_ITEM, rose.base_name_of("  Conjured Aged Brie"));
+        }
+
+        #[test]
+        fn test_conjured_names_collapse_whitespace() {
+            // GIVEN conjured items with stray whitespace, and some in odd case for a shop ignoring
+            // case
+            let spaced = vec![
+                Item::new("Conjured  Aged Brie", 5, 10),
+                Item::new("   Conjured Aged Brie", 5, 10),
+                Item::new("Conjured\tAged   Brie ", 5, 10),
+            ];
+            let mixed_case = vec![
+                Item::new("conjured  aged brie", 5, 10),
+                Item::new(" CoNjUrEd Mana  Cake", 5, 10),
+            ];
+            let mut spaced = GildedRose::new(spaced);
+            let mut mixed_case = GildedRose::new(mixed_case).with_case_insensitive_names(true);
+
+            // WHEN updating quality
+            spaced.update_quality();
+            mixed_case.update_quality();
+
+            // THEN they should behave as conjured items, keeping their names as given
+            assert_eq!(Item::new("Conjured  Aged Brie", 4, 12), spaced.items[0]);
+            assert_eq!(Item::new("   Conjured Aged Brie", 4, 12), spaced.items[1]);
+            assert_eq!(Item::new("Conjured\tAged   Brie ", 4, 12), spaced.items[2]);
+            assert_eq!(Item::new("conjured  aged brie", 4, 12), mixed_case.items[0]);
+            assert_eq!(Item::new(" CoNjUrEd Mana  Cake", 4, 8), mixed_case.items[1]);
+        }
+    }
+
+    mod sell_in_floor_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_minimum_sell_in_does_not_overflow() {
+            // GIVEN items whose sell_in cannot go any lower
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, i32::MIN, 7),
+                Item::new(AGED_BRIE_ITEM, i32::MIN, 7),
+                Item::new(BACKSTAGE_PASSES_ITEM, i32::MIN, 7),
+                Item::new(CONJURED_ITEM, i32::MIN, 7),
+            ]);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN sell_in should stay at its minimum
+            assert!(rose.items.iter().all(|item| item.sell_in == i32::MIN));
+        }
+
+        #[test]
+        fn test_floor_stops_sell_in() {
+            // GIVEN a shop with a sell_in floor of -365
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, -1000, 7),
+            ]).with_sell_in_floor(Some(-365));
+
+            // WHEN updating for well over a year
+            rose.update_quality_days(500);
+
+            // THEN sell_in should stop at the floor, leaving items already below it alone
+            assert_eq!(-365, rose.items[0].sell_in);
+            assert_eq!(-1000, rose.items[1].sell_in);
+        }
+    }
+
+    mod from_tuples_feature {
+        use gildedrose::tests::{CONJURED_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_from_tuples() {
+            let rose = GildedRose::from_tuples([(AGED_BRIE_ITEM, 2, 0), (CONJURED_ITEM, 3, 6)]);
+
+            assert_eq!(vec![
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(CONJURED_ITEM, 3, 6),
+            ], rose.items);
+        }
+    }
+
+    mod value_trend_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{Item, Trend};
+
+        #[test]
+        fn test_days_until_expiry() {
+            assert_eq!(5, Item::new(COMMON_ITEM, 5, 7).days_until_expiry());
+            assert_eq!(-2, Item::new(COMMON_ITEM, -2, 7).days_until_expiry());
+        }
+
+        #[test]
+        fn test_trend_per_item_type() {
+            let cases = [
+                (Item::new(COMMON_ITEM, 5, 7), Trend::Decreasing),
+                (Item::new(COMMON_ITEM, -1, 7), Trend::Decreasing),
+                (Item::new(CONJURED_ITEM, 3, 6), Trend::Decreasing),
+                (Item::new(AGED_BRIE_ITEM, 2, 0), Trend::Increasing),
+                (Item::new(AGED_BRIE_ITEM, -5, 50), Trend::Increasing),
+                (Item::new(LEGENDARY_ITEM, 0, 80), Trend::Stable),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 15, 20), Trend::Increasing),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 1, 20), Trend::Increasing),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 0, 20), Trend::Decreasing),
+                (Item::new(BACKSTAGE_PASSES_ITEM, -1, 0), Trend::Stable),
+            ];
+
+            for &(ref item, trend) in &cases {
+                assert_eq!(trend, item.value_trend(), "{}", item);
+            }
+        }
+    }
+
+    mod closure_strategy_feature {
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_closure_halving_quality() {
+            // GIVEN a widget whose quality halves every day
+            let mut rose = GildedRose::new(vec![
+                Item::new("Weird Widget", 5, 40),
+            ]);
+            rose.register_strategy("Weird Widget", Box::new(|item: &Item| item.quality / 2));
+
+            // WHEN updating quality for three days
+            // THEN quality should halve each time
+            for &expected in &[20, 10, 5] {
+                rose.update_quality();
+                assert_eq!(expected, rose.items[0].quality);
+            }
+            assert_eq!(2, rose.items[0].sell_in);
+        }
+
+        #[test]
+        fn test_register_fn_infers_argument() {
+            // GIVEN a widget registered with a closure whose argument is not typed
+            let mut rose = GildedRose::new(vec![
+                Item::new("Weird Widget", 5, 40),
+            ]);
+            rose.register_fn("Weird Widget", |item| item.quality / 2);
+
+            // WHEN updating quality twice
+            rose.update_quality_days(2);
+
+            // THEN quality should have halved twice
+            assert_eq!(Item::new("Weird Widget", 3, 10), rose.items[0]);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod group_by_kind_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, ItemKind};
+
+        #[test]
+        fn test_group_mixed_inventory() {
+            // GIVEN a mixed inventory
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new("+5 Dexterity Vest", 10, 20),
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+                Item::new(LEGENDARY_ITEM, -1, 80),
+            ]);
+
+            // WHEN grouping by kind
+            let groups = rose.group_by_kind();
+
+            // THEN every present kind should have its items, in inventory order
+            assert_eq!(3, groups.len());
+            assert_eq!(vec![&rose.items[0], &rose.items[2]], groups[&ItemKind::Common]);
+            assert_eq!(vec![&rose.items[1], &rose.items[4]], groups[&ItemKind::Legendary]);
+            assert_eq!(vec![&rose.items[3]], groups[&ItemKind::BackstagePass]);
+            assert!(!groups.contains_key(&ItemKind::AgedBrie));
+        }
+
+        #[test]
+        fn test_count_by_kind() {
+            // GIVEN a mixed inventory
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new("+5 Dexterity Vest", 10, 20),
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+                Item::new(LEGENDARY_ITEM, -1, 80),
+            ]);
+
+            // WHEN counting by kind
+            let counts = rose.count_by_kind();
+
+            // THEN every present kind should have its count
+            assert_eq!(3, counts.len());
+            assert_eq!(2, counts[&ItemKind::Common]);
+            assert_eq!(2, counts[&ItemKind::Legendary]);
+            assert_eq!(1, counts[&ItemKind::BackstagePass]);
+            assert!(GildedRose::default().count_by_kind().is_empty());
+        }
+    }
+
+    mod property_feature {
+        use proptest::prelude::*;
+
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn name() -> impl Strategy<Value = String> {
+            prop_oneof![
+                Just(COMMON_ITEM.to_string()),
+                Just(CONJURED_ITEM.to_string()),
+                Just(AGED_BRIE_ITEM.to_string()),
+                Just(BACKSTAGE_PASSES_ITEM.to_string()),
+                Just(format!("Conjured {}", AGED_BRIE_ITEM)),
+                Just(format!("Conjured {}", BACKSTAGE_PASSES_ITEM)),
+                "[A-Za-z ]{1,20}",
+            ]
+        }
+
+        fn item() -> impl Strategy<Value = Item> {
+            (name(), -20..20, 0..=50).prop_map(|(name, sell_in, quality)| Item::new(name, sell_in, quality))
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(1000))]
+
+            #[test]
+            fn test_invariants_hold(items in prop::collection::vec(item(), 1..10), days in 1..=50u32) {
+                // GIVEN any inventory, with a legendary item in it
+                let mut items = items;
+                items.push(Item::new(LEGENDARY_ITEM, 0, 80));
+                let mut rose = GildedRose::new(items);
+
+                // WHEN updating any number of days
+                for _ in 0..days {
+                    rose.update_quality();
+
+                    // THEN quality should stay within the bounds, legendary items at 80 and
+                    // backstage passes at 0 after the concert
+                    for item in &rose.items {
+                        if item.name == LEGENDARY_ITEM {
+                            prop_assert_eq!(80, item.quality);
+                        } else {
+                            prop_assert!((0..=50).contains(&item.quality), "{}", item);
+                        }
+                        if item.name == BACKSTAGE_PASSES_ITEM && item.sell_in < 0 {
+                            prop_assert_eq!(0, item.quality);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    mod apply_until_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_until_quality_reaches_zero() {
+            // GIVEN a common item that loses 1 quality for 3 days and 2 after that
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 3, 10),
+            ]);
+
+            // WHEN advancing until it is worthless
+            let days = rose.apply_until(|rose| rose.items[0].quality == 0, 100);
+
+            // THEN it should take 3 days at 1 and 4 days at 2
+            assert_eq!(7, days);
+            assert_eq!(0, rose.items[0].quality);
+
+            // ...and advancing again should do nothing
+            assert_eq!(0, rose.apply_until(|rose| rose.items[0].quality == 0, 100));
+        }
+
+        #[test]
+        fn test_max_days_guard() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ]);
+
+            let days = rose.apply_until(|rose| rose.items[0].quality < 80, 30);
+
+            assert_eq!(30, days);
+        }
+    }
+
+    mod daily_quality_delta_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{Item, daily_quality_delta};
+
+        #[test]
+        fn test_delta_per_item_type() {
+            let cases = [
+                (Item::new(COMMON_ITEM, 5, 7), -1),
+                (Item::new(COMMON_ITEM, 0, 7), -2),
+                (Item::new(COMMON_ITEM, 5, 0), -1),
+                (Item::new(CONJURED_ITEM, 3, 6), -2),
+                (Item::new(CONJURED_ITEM, -1, 6), -4),
+                (Item::new(AGED_BRIE_ITEM, 2, 0), 1),
+                (Item::new(AGED_BRIE_ITEM, 0, 50), 2),
+                (Item::new(LEGENDARY_ITEM, 0, 80), 0),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 11, 20), 1),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 10, 20), 2),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 5, 20), 3),
+                (Item::new(BACKSTAGE_PASSES_ITEM, 0, 20), -20),
+            ];
+
+            for &(ref item, delta) in &cases {
+                assert_eq!(delta, daily_quality_delta(item), "{}", item);
+            }
+        }
+    }
+
+    mod appreciating_max_quality_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_brie_exceeds_common_cap() {
+            // GIVEN a shop where appreciating items may reach 70
+            let mut rose = GildedRose::new(vec![
+                Item::new(AGED_BRIE_ITEM, 0, 48),
+                Item::new(COMMON_ITEM, 0, 60),
+                Item::new(BACKSTAGE_PASSES_ITEM, 5, 49),
+                Item::new("Conjured Aged Brie", 0, 48),
+            ]).with_appreciating_max_quality(70);
+
+            // WHEN updating quality once
+            rose.update_quality();
+
+            // THEN the appreciating items should pass 50 while the common item is capped at it
+            assert_eq!(50, rose.items[0].quality);
+            assert_eq!(50, rose.items[1].quality);
+            assert_eq!(52, rose.items[2].quality);
+            assert_eq!(52, rose.items[3].quality);
+            assert!(rose.validate().is_empty());
+
+            // ...and WHEN updating for 20 more days
+            rose.update_quality_days(20);
+
+            // THEN brie should stop at 70
+            assert_eq!(70, rose.items[0].quality);
+            assert_eq!(10, rose.items[1].quality);
+            assert_eq!(0, rose.items[2].quality);
+            assert_eq!(70, rose.items[3].quality);
+        }
+    }
+
+    mod parse_item_feature {
+        use std::convert::TryFrom;
+
+        use gildedrose::tests::{BACKSTAGE_PASSES_ITEM};
+        use super::{Item, ItemParseError};
+
+        #[test]
+        fn test_display_round_trip() {
+            let item = Item::new(BACKSTAGE_PASSES_ITEM, 15, 20);
+
+            assert_eq!(Ok(item.clone()), Item::try_from(item.to_string().as_str()));
+        }
+
+        #[test]
+        fn test_name_with_commas() {
+            // GIVEN a hand-written line whose name contains a comma
+            let line = "Sulfuras, Hand of Ragnaros, -1, 80";
+
+            // WHEN parsing it
+            // THEN the last two fields should be the numbers
+            assert_eq!(Ok(Item::new("Sulfuras, Hand of Ragnaros", -1, 80)), Item::try_from(line));
+        }
+
+        #[test]
+        fn test_parse_errors() {
+            assert_eq!(Err(ItemParseError::MissingFields), Item::try_from("Aged Brie, 2"));
+            assert_eq!(
+                Err(ItemParseError::InvalidNumber { field: "quality", value: "lots".to_string() }),
+                Item::try_from("Aged Brie, 2, lots")
+            );
+        }
+
+        #[test]
+        fn test_negative_sell_in() {
+            // GIVEN a line for an expired item
+            let line = "Elixir of the Mongoose, -5, 0";
+
+            // WHEN parsing it
+            let item = Item::try_from(line).unwrap();
+
+            // THEN the minus sign should be kept
+            assert_eq!(-5, item.sell_in);
+            assert_eq!(line, item.to_string());
+        }
+    }
+
+    mod fresh_item_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::Item;
+
+        #[test]
+        fn test_fresh_defaults() {
+            assert_eq!(Item::new(LEGENDARY_ITEM, 0, 80), Item::fresh(LEGENDARY_ITEM));
+            assert_eq!(Item::new(COMMON_ITEM, 10, 20), Item::fresh(COMMON_ITEM));
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 10, 0), Item::fresh(AGED_BRIE_ITEM));
+            assert_eq!(Item::new(BACKSTAGE_PASSES_ITEM, 15, 20), Item::fresh(BACKSTAGE_PASSES_ITEM));
+            assert_eq!(Item::new(CONJURED_ITEM, 5, 20), Item::fresh(CONJURED_ITEM));
+        }
+
+        #[test]
+        fn test_fresh_items_are_valid() {
+            for name in &[COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM] {
+                let item = Item::fresh(*name);
+                let built = Item::builder().name(item.name.clone()).sell_in(item.sell_in).quality(item.quality).build();
+                assert_eq!(Ok(item), built);
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod weighted_value_feature {
+        use std::collections::HashMap;
+
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemKind};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 10),
+            ])
+        }
+
+        #[test]
+        fn test_custom_weights() {
+            // GIVEN weights favoring legendary items, without one for common items
+            let mut weights = HashMap::new();
+            weights.insert(ItemKind::Legendary, 2.5);
+            weights.insert(ItemKind::AgedBrie, 0.5);
+
+            // WHEN valuing the inventory
+            // THEN every quality should count by its kind's weight
+            assert_eq!(7.0 + 200.0 + 5.0, inventory().weighted_value(&weights));
+        }
+
+        #[test]
+        fn test_no_weights_sums_quality() {
+            assert_eq!(97.0, inventory().weighted_value(&HashMap::new()));
+        }
+    }
+
+    mod conjured_multiplier_feature {
+        use gildedrose::tests::{CONJURED_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ConjuredStrategy, DefaultStrategy, UpdateStrategy};
+
+        const CONJURED_AGED_BRIE_ITEM: &str = "Conjured Aged Brie";
+
+        #[test]
+        fn test_default_multiplier_doubles() {
+            let mut rose = GildedRose::new(vec![
+                Item::new("Conjured++ Mana Cake", 1, 20),
+            ]);
+            rose.register_strategy("Conjured++ Mana Cake", Box::new(ConjuredStrategy::default()));
+
+            rose.update_quality();
+            assert_eq!(18, rose.items[0].quality);
+            rose.update_quality();
+            assert_eq!(14, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_tripled_degradation() {
+            // GIVEN a conjured variant that degrades three times as fast
+            let mut rose = GildedRose::new(vec![
+                Item::new("Conjured++ Mana Cake", 1, 20),
+                Item::new(CONJURED_ITEM, 1, 20),
+            ]);
+            rose.register_strategy("Conjured++ Mana Cake", Box::new(ConjuredStrategy::with_multiplier(DefaultStrategy::new(), 3)));
+
+            // WHEN updating quality before and after the sell by date
+            // THEN it should lose 3 and then 6 while the regular conjured item loses 2 and then 4
+            rose.update_quality();
+            assert_eq!((17, 18), (rose.items[0].quality, rose.items[1].quality));
+            rose.update_quality();
+            assert_eq!((11, 14), (rose.items[0].quality, rose.items[1].quality));
+        }
+
+        #[test]
+        fn test_shop_multiplier() {
+            // GIVEN a shop whose conjured items change three times as fast
+            let mut rose = GildedRose::new(vec![
+                Item::new(CONJURED_ITEM, 1, 20),
+                Item::new(CONJURED_AGED_BRIE_ITEM, 5, 20),
+            ]).with_conjured_multiplier(3);
+
+            // WHEN updating quality before and after the sell by date
+            // THEN conjured items should lose and gain three times as much
+            rose.update_quality();
+            assert_eq!((17, 23), (rose.items[0].quality, rose.items[1].quality));
+            rose.update_quality();
+            assert_eq!((11, 26), (rose.items[0].quality, rose.items[1].quality));
+        }
+
+        #[test]
+        fn test_strategy_for_matches_dispatch() {
+            // GIVEN a shop with a multiplier and a registration for the base of a conjured name
+            let mut rose = GildedRose::new(vec![
+                Item::new(CONJURED_ITEM, 5, 20),
+                Item::new(CONJURED_AGED_BRIE_ITEM, 5, 20),
+            ]).with_conjured_multiplier(3);
+            rose.register_strategy(AGED_BRIE_ITEM, Box::new(|item: &Item| item.quality + 2));
+
+            // WHEN looking up the strategy for each conjured name
+            // THEN it should work out the same next state as an update
+            for index in 0..rose.len() {
+                let item = &rose.items[index];
+                let expected = rose.peek_next(index).unwrap();
+                assert_eq!((expected.sell_in, expected.quality), rose.strategy_for(&item.name).next_state(item, 0, 50));
+            }
+            assert_eq!(26, rose.peek_next(1).unwrap().quality);
+        }
+
+        #[test]
+        fn test_shop_follows_conjured_strategy() {
+            // GIVEN a shop tripling conjured items and a strategy tripling common items
+            let rose = GildedRose::new(vec![Item::new(CONJURED_ITEM, 0, 20)]).with_conjured_multiplier(3);
+            let strategy = ConjuredStrategy::with_multiplier(DefaultStrategy::new(), 3);
+
+            // WHEN working out the next state of the conjured item both ways
+            // THEN both should lose six times as much as a common item's 1
+            let item = &rose.items[0];
+            assert_eq!((-1, 14), strategy.next_state(item, 0, 50));
+            assert_eq!((-1, 14), rose.strategy_for(&item.name).next_state(item, 0, 50));
+        }
+    }
+
+    mod partition_expired_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_partition_mixed_inventory() {
+            // GIVEN a mixed inventory
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, -1, 5),
+                Item::new(AGED_BRIE_ITEM, 0, 10),
+                Item::new(BACKSTAGE_PASSES_ITEM, -3, 0),
+                Item::new(COMMON_ITEM, 4, 7),
+            ]);
+
+            // WHEN partitioning off the expired items
+            let (fresh, expired) = rose.partition_expired();
+
+            // THEN each item should land in its bucket, in order
+            assert_eq!(vec![
+                Item::new(AGED_BRIE_ITEM, 0, 10),
+                Item::new(COMMON_ITEM, 4, 7),
+            ], fresh.items);
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, -1, 5),
+                Item::new(BACKSTAGE_PASSES_ITEM, -3, 0),
+            ], expired.items);
+        }
+    }
+
+    mod dedup_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(COMMON_ITEM, 5, 8),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ])
+        }
+
+        #[test]
+        fn test_dedup_consecutive() {
+            let mut rose = inventory();
+
+            rose.dedup();
+
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(COMMON_ITEM, 5, 8),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ], rose.items);
+        }
+
+        #[test]
+        fn test_dedup_scattered() {
+            let mut rose = inventory();
+
+            rose.dedup_all();
+
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(COMMON_ITEM, 5, 8),
+            ], rose.items);
+        }
+    }
+
+    mod update_item_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, update_item};
+
+        #[test]
+        fn test_standalone_matches_inventory() {
+            // GIVEN standalone items and a shop holding copies of them
+            let mut items = vec![
+                Item::new(COMMON_ITEM, 0, 7),
+                Item::new(CONJURED_ITEM, 3, 6),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 49),
+                Item::new(BACKSTAGE_PASSES_ITEM, 5, 20),
+            ];
+            let mut rose = GildedRose::new(items.clone());
+
+            // WHEN updating each standalone item and the shop for 10 days
+            for _ in 0..10 {
+                for item in items.iter_mut() {
+                    update_item(item, 0, 50);
+                }
+                rose.update_quality();
+            }
+
+            // THEN both should end up the same
+            assert_eq!(rose.items, items);
+        }
+    }
+
+    mod quality_direction_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{Item, ItemKind};
+
+        #[test]
+        fn test_direction_per_kind() {
+            let cases = [
+                (ItemKind::AgedBrie, Item::new(AGED_BRIE_ITEM, 2, 0), 1),
+                (ItemKind::AgedBrie, Item::new(AGED_BRIE_ITEM, -3, 10), 1),
+                (ItemKind::BackstagePass, Item::new(BACKSTAGE_PASSES_ITEM, 15, 20), 1),
+                (ItemKind::BackstagePass, Item::new(BACKSTAGE_PASSES_ITEM, 1, 20), 1),
+                (ItemKind::BackstagePass, Item::new(BACKSTAGE_PASSES_ITEM, 0, 20), -1),
+                (ItemKind::BackstagePass, Item::new(BACKSTAGE_PASSES_ITEM, -1, 0), 0),
+                (ItemKind::Common, Item::new(COMMON_ITEM, 5, 7), -1),
+                (ItemKind::Common, Item::new(COMMON_ITEM, -1, 7), -1),
+                (ItemKind::Conjured, Item::new(CONJURED_ITEM, 3, 6), -1),
+                (ItemKind::Conjured, Item::new("Conjured Aged Brie", 2, 0), 1),
+                (ItemKind::Conjured, Item::new("Conjured Backstage passes to a TAFKAL80ETC concert", 3, 20), 1),
+                (ItemKind::Conjured, Item::new("Conjured Backstage passes to a TAFKAL80ETC concert", 0, 20), -1),
+                (ItemKind::Legendary, Item::new(LEGENDARY_ITEM, 0, 80), 0),
+                (ItemKind::Legendary, Item::new(LEGENDARY_ITEM, -1, 80), 0),
+            ];
+
+            for &(kind, ref item, direction) in &cases {
+                assert_eq!(direction, kind.quality_direction(item), "{}", item);
+            }
+        }
+    }
+
+    mod name_config_feature {
+        use gildedrose::tests::{LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, NameConfig};
+
+        #[test]
+        fn test_renamed_items() {
+            // GIVEN a themed shop that renames the brie and the legendary item
+            let names = NameConfig {
+                aged_brie: "Fine Wine".to_string(),
+                legendary: "Excalibur".to_string(),
+                ..NameConfig::default()
+            };
+            let mut rose = GildedRose::with_names(vec![
+                Item::new("Fine Wine", 2, 0),
+                Item::new("Excalibur", 0, 80),
+                Item::new("Conjured Fine Wine", 2, 0),
+                Item::new(AGED_BRIE_ITEM, 2, 10),
+                Item::new(LEGENDARY_ITEM, 0, 40),
+            ], names);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN the new names should get the special behavior and the old ones should not
+            assert_eq!(vec![
+                Item::new("Fine Wine", 1, 1),
+                Item::new("Excalibur", 0, 80),
+                Item::new("Conjured Fine Wine", 1, 2),
+                Item::new(AGED_BRIE_ITEM, 1, 9),
+                Item::new(LEGENDARY_ITEM, -1, 38),
+            ], rose.items);
+        }
+    }
+
+    mod index_accessor_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_get_in_range() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+
+            assert_eq!(Some(&Item::new(AGED_BRIE_ITEM, 2, 0)), rose.get(1));
+            rose.get_mut(0).unwrap().quality = 3;
+            assert_eq!(3, rose.items[0].quality);
+        }
+
+        #[test]
+        fn test_get_out_of_range() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+            ]);
+
+            assert_eq!(None, rose.get(1));
+            assert_eq!(None, rose.get_mut(usize::MAX));
+            assert_eq!(None, GildedRose::new(Vec::new()).get(0));
+        }
+    }
+
+    mod update_stats_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, UpdateStats};
+
+        #[test]
+        fn test_counters() {
+            // GIVEN items that hit each counter, and some that hit none
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 0),
+                Item::new(CONJURED_ITEM, 5, 1),
+                Item::new(AGED_BRIE_ITEM, 5, 50),
+                Item::new(BACKSTAGE_PASSES_ITEM, 3, 49),
+                Item::new(COMMON_ITEM, 0, 10),
+                Item::new(COMMON_ITEM, -1, 10),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(COMMON_ITEM, 5, 10),
+            ]);
+
+            // WHEN updating quality with stats
+            let stats = rose.update_quality_stats();
+
+            // THEN the floor, ceiling and expiry should each be counted
+            assert_eq!(UpdateStats { clamped_to_min: 2, clamped_to_max: 2, expired_this_tick: 1 }, stats);
+            assert_eq!(Item::new(CONJURED_ITEM, 4, 0), rose.items[1]);
+            assert_eq!(Item::new(COMMON_ITEM, -1, 8), rose.items[4]);
+        }
+
+        #[test]
+        fn test_quiet_tick() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 10),
+            ]);
+
+            assert_eq!(UpdateStats::default(), rose.update_quality_stats());
+        }
+
+        #[test]
+        fn test_middleware_is_not_clamping() {
+            // GIVEN a common item kept at 5 by middleware, and one at the minimum
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 6),
+                Item::new(COMMON_ITEM, 5, 0),
+            ]);
+            rose.set_middleware(Box::new(|item: &Item, quality: i32, sell_in: i32| {
+                (quality.max(5.min(item.quality)), sell_in)
+            }));
+
+            // WHEN updating quality with stats twice
+            rose.update_quality_stats();
+            let stats = rose.update_quality_stats();
+
+            // THEN only the item limited by the bound should count as clamped
+            assert_eq!(UpdateStats { clamped_to_min: 1, clamped_to_max: 0, expired_this_tick: 0 }, stats);
+            assert_eq!(Item::new(COMMON_ITEM, 3, 5), rose[0]);
+        }
+    }
+
+    mod backstage_target_feature {
+        use gildedrose::tests::{BACKSTAGE_PASSES_ITEM};
+        use super::{Item, backstage_target};
+
+        #[test]
+        fn test_target_near_ceiling() {
+            assert_eq!((52, 50), backstage_target(&Item::new(BACKSTAGE_PASSES_ITEM, 3, 49)));
+        }
+
+        #[test]
+        fn test_target_with_room() {
+            assert_eq!((23, 23), backstage_target(&Item::new(BACKSTAGE_PASSES_ITEM, 3, 20)));
+            assert_eq!((0, 0), backstage_target(&Item::new(BACKSTAGE_PASSES_ITEM, 0, 20)));
+        }
+    }
+
+    mod default_shop_feature {
+        use super::GildedRose;
+
+        #[test]
+        fn test_default_is_empty_with_default_bounds() {
+            let rose = GildedRose::default();
+
+            assert!(rose.is_empty());
+            assert_eq!((0, 50), (rose.min_quality(), rose.max_quality()));
+        }
+    }
+
+    mod merge_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn warehouses() -> (GildedRose, GildedRose) {
+            let north = GildedRose::with_bounds(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ], 0, 100);
+            let south = GildedRose::new(vec![
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+            ]);
+            (north, south)
+        }
+
+        fn merged_items() -> Vec<Item> {
+            vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+            ]
+        }
+
+        #[test]
+        fn test_merge() {
+            // GIVEN two warehouses
+            let (mut north, south) = warehouses();
+
+            // WHEN merging the second into the first
+            north.merge(south);
+
+            // THEN the first should hold all items in order and keep its bounds
+            assert_eq!(3, north.len());
+            assert_eq!(merged_items(), north.items);
+            assert_eq!((0, 100), (north.min_quality(), north.max_quality()));
+        }
+
+        #[test]
+        fn test_concat() {
+            let (north, south) = warehouses();
+
+            let rose = GildedRose::concat(north, south);
+
+            assert_eq!(merged_items(), rose.items);
+            assert_eq!((0, 100), (rose.min_quality(), rose.max_quality()));
+        }
+    }
+
+    mod degrade_rate_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_new_has_no_degrade_rate() {
+            assert_eq!(None, Item::new(COMMON_ITEM, 5, 7).degrade_rate);
+        }
+
+        #[test]
+        fn test_contract_degrade_rate() {
+            // GIVEN a common item that degrades by 3 a day by contract, and a conjured one
+            let mut rose = GildedRose::new(vec![
+                Item { degrade_rate: Some(3), ..Item::new(COMMON_ITEM, 1, 30) },
+                Item { degrade_rate: Some(3), ..Item::new(CONJURED_ITEM, 1, 30) },
+            ]);
+
+            // WHEN updating quality before and after the sell by date
+            // THEN it should lose 3 and then 6, twice that when conjured
+            rose.update_quality();
+            assert_eq!((27, 24), (rose.items[0].quality, rose.items[1].quality));
+            rose.update_quality();
+            assert_eq!((21, 12), (rose.items[0].quality, rose.items[1].quality));
+        }
+    }
+
+    mod most_valuable_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_ties_resolved_by_sell_in() {
+            // GIVEN an inventory with ties on the highest and lowest quality
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 2),
+                Item::new(BACKSTAGE_PASSES_ITEM, 10, 30),
+                Item::new(AGED_BRIE_ITEM, 2, 2),
+                Item::new(COMMON_ITEM, 4, 30),
+                Item::new(COMMON_ITEM, 1, 10),
+            ]);
+
+            // WHEN asking for the most and least valuable items
+            // THEN the ones with the sooner sell_in should win the ties
+            assert_eq!(Some(&Item::new(COMMON_ITEM, 4, 30)), rose.most_valuable());
+            assert_eq!(Some(&Item::new(AGED_BRIE_ITEM, 2, 2)), rose.least_valuable());
+        }
+
+        #[test]
+        fn test_empty_inventory() {
+            let rose = GildedRose::default();
+
+            assert_eq!(None, rose.most_valuable());
+            assert_eq!(None, rose.least_valuable());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod ndjson_feature {
+        use gildedrose::NdjsonError;
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_clean_ndjson() {
+            let ndjson = "{ \"name\": \"Elixir of the Mongoose\", \"sell_in\": 5, \"quality\": 7 }\n\
+                          { \"name\": \"Aged Brie\", \"sell_in\": 2, \"quality\": 0 }\n";
+
+            let rose = GildedRose::from_ndjson(ndjson.as_bytes()).unwrap();
+
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ], rose.items);
+        }
+
+        #[test]
+        fn test_blank_lines_skipped() {
+            let ndjson = "\n{ \"name\": \"Aged Brie\", \"sell_in\": 2, \"quality\": 0 }\n   \n\n";
+
+            let rose = GildedRose::from_ndjson(ndjson.as_bytes()).unwrap();
+
+            assert_eq!(vec![Item::new(AGED_BRIE_ITEM, 2, 0)], rose.items);
+        }
+
+        #[test]
+        fn test_bad_line_number() {
+            // GIVEN JSON lines with a broken third line
+            let ndjson = "{ \"name\": \"Aged Brie\", \"sell_in\": 2, \"quality\": 0 }\n\
+                          \n\
+                          { \"name\": \"Aged Brie\", \"sell_in\": 2 }\n";
+
+            // WHEN loading them
+            // THEN the error should carry the line number
+            match GildedRose::from_ndjson(ndjson.as_bytes()) {
+                Err(NdjsonError::Parse { line, .. }) => assert_eq!(3, line),
+                _ => panic!("expected a parse error"),
+            }
+        }
+
+        #[test]
+        fn test_negative_sell_in() {
+            let ndjson = "{ \"name\": \"Elixir of the Mongoose\", \"sell_in\": -5, \"quality\": 0 }\n";
+
+            let rose = GildedRose::from_ndjson(ndjson.as_bytes()).unwrap();
+
+            assert_eq!(-5, rose.items[0].sell_in);
+        }
+    }
+
+    mod tick_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 1, 7),
+                Item::new(CONJURED_ITEM, 3, 6),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 6, 20),
+            ])
+        }
+
+        #[test]
+        fn test_tick_matches_update_quality() {
+            let (mut ticked, mut updated) = (inventory(), inventory());
+
+            ticked.tick();
+            updated.update_quality();
+            assert_eq!(updated.items, ticked.items);
+
+            ticked.tick_days(7);
+            updated.update_quality_days(7);
+            assert_eq!(updated.items, ticked.items);
+        }
+    }
+
+    mod conjured_backstage_feature {
+        use super::{GildedRose, Item, BackstagePassStrategy, ConjuredStrategy};
+
+        const CONJURED_PASS: &str = "Conjured Backstage passes to a TAFKAL80ETC concert";
+
+        #[test]
+        fn test_conjured_pass_gains_double_then_zeroes() {
+            // GIVEN a conjured backstage pass close to the concert
+            let mut rose = GildedRose::new(vec![
+                Item::new(CONJURED_PASS, 1, 20),
+            ]);
+
+            // WHEN updating quality the day before the concert
+            rose.update_quality();
+
+            // THEN it should gain twice the last tier
+            assert_eq!(Item::new(CONJURED_PASS, 0, 26), rose.items[0]);
+
+            // ...and WHEN updating quality on the concert day
+            rose.update_quality();
+
+            // THEN it should be exactly 0
+            assert_eq!(Item::new(CONJURED_PASS, -1, 0), rose.items[0]);
+        }
+
+        #[test]
+        fn test_zeroing_is_not_doubled_below_zero() {
+            // GIVEN shops allowing negative quality, with conjured passes by prefix and by wrapper
+            let items = vec![
+                Item::new(CONJURED_PASS, 0, 20),
+                Item::new("Conjured Ticket", 0, 20),
+            ];
+            let mut rose = GildedRose::with_bounds(items, -10, 50);
+            let strategy = ConjuredStrategy::new(BackstagePassStrategy::new());
+            rose.register_strategy("Conjured Ticket", Box::new(strategy));
+
+            // WHEN updating quality after the concert
+            rose.update_quality();
+
+            // THEN both should drop to 0 rather than lose twice their quality
+            assert_eq!(0, rose.items[0].quality);
+            assert_eq!(0, rose.items[1].quality);
+        }
+    }
+
+    mod trajectory_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM};
+        use super::{Item, update_item};
+
+        #[test]
+        fn test_trajectory_matches_manual_updates() {
+            // GIVEN a common item
+            let item = Item::new(COMMON_ITEM, 2, 7);
+
+            // WHEN taking the first five states of its trajectory
+            let states: Vec<Item> = item.trajectory(0, 50).take(5).collect();
+
+            // THEN they should match updating a copy day by day, leaving the item untouched
+            let mut manual = item.clone();
+            let expected: Vec<Item> = (0..5).map(|_| {
+                update_item(&mut manual, 0, 50);
+                manual.clone()
+            }).collect();
+            assert_eq!(expected, states);
+            assert_eq!(Item::new(COMMON_ITEM, 2, 7), item);
+        }
+
+        #[test]
+        fn test_trajectory_settles() {
+            let common = Item::new(COMMON_ITEM, 1, 3);
+            let legendary = Item::new(LEGENDARY_ITEM, 5, 80);
+
+            assert_eq!(Some(Item::new(COMMON_ITEM, -9, 0)), common.trajectory(0, 50).nth(9));
+            assert!(legendary.trajectory(0, 50).take(20).all(|state| state == legendary));
+        }
+    }
+
+    mod expiry_threshold_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_grace_period_before_double_decay() {
+            // GIVEN a shop with a grace period of two days past the sell by date
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, -1, 20),
+                Item::new(COMMON_ITEM, -3, 20),
+                Item::new(CONJURED_ITEM, -1, 20),
+                Item::new(BACKSTAGE_PASSES_ITEM, 0, 20),
+            ]).with_expiry_threshold(-2);
+
+            // WHEN updating quality
+            rose.update_quality();
+
+            // THEN only items past the grace period should decay twice as fast
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, -2, 19),
+                Item::new(COMMON_ITEM, -4, 18),
+                Item::new(CONJURED_ITEM, -2, 18),
+                Item::new(BACKSTAGE_PASSES_ITEM, -1, 0),
+            ], rose.items);
+        }
+    }
+
+    mod classification_cache_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        const NAMES: &[&str] = &[
+            COMMON_ITEM,
+            CONJURED_ITEM,
+            LEGENDARY_ITEM,
+            AGED_BRIE_ITEM,
+            BACKSTAGE_PASSES_ITEM,
+            "Conjured Aged Brie",
+            "Conjured Backstage passes to a TAFKAL80ETC concert",
+            " aged brie ",
+        ];
+
+        /// A small xorshift generator, so the inventory is the same on every run.
+        fn inventory(size: usize) -> Vec<Item> {
+            let mut state: u32 = 0x2545_f491;
+            let mut next = move || {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            };
+            (0..size).map(|_| {
+                let name = NAMES[next() as usize % NAMES.len()];
+                Item::new(name, (next() % 30) as i32 - 10, (next() % 51) as i32)
+            }).collect()
+        }
+
+        /// The items after one update, each worked out without the cache.
+        fn uncached_update(rose: &GildedRose) -> Vec<Item> {
+            (0..rose.len()).map(|index| rose.peek_next(index).unwrap()).collect()
+        }
+
+        #[test]
+        fn test_cached_updates_match_uncached_path() {
+            // GIVEN a large randomized inventory
+            let mut rose = GildedRose::new(inventory(10_000)).with_case_insensitive_names(true);
+
+            for day in 0..20 {
+                // WHEN renaming and removing items between some of the updates
+                if day % 5 == 4 {
+                    let name = NAMES[day % NAMES.len()].to_string();
+                    for item in (&mut rose).into_iter().step_by(7) {
+                        item.name = name.clone();
+                    }
+                    rose.remove(day);
+                }
+                let expected = uncached_update(&rose);
+                rose.update_quality();
+
+                // THEN every update should match classifying the items afresh
+                assert_eq!(expected, rose.items, "day {}", day);
+            }
+        }
+
+        #[test]
+        fn test_cache_follows_renamed_items() {
+            // GIVEN a shop whose items have been classified by an update
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, 5, 10),
+            ]);
+            rose.update_quality();
+
+            // WHEN renaming items directly and through the shop, and registering a strategy for
+            // another name
+            rose.items[0].name = AGED_BRIE_ITEM.to_string();
+            rose.find_mut(COMMON_ITEM).unwrap().name = LEGENDARY_ITEM.to_string();
+            rose.register_strategy(COMMON_ITEM, Box::new(|item: &Item| item.quality + 5));
+            rose.update_quality();
+
+            // THEN the next update should follow the new names and registration
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 3, 10), rose[0]);
+            assert_eq!(Item::new(LEGENDARY_ITEM, 4, 9), rose[1]);
+            assert_eq!(Item::new(COMMON_ITEM, 3, 14), rose[2]);
+        }
+    }
+
+    mod retain_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, -1, 0),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, -3, 0),
+                Item::new(COMMON_ITEM, 4, 7),
+            ])
+        }
+
+        #[test]
+        fn test_retain_unexpired() {
+            // GIVEN a shop with expired items
+            let mut rose = inventory();
+
+            // WHEN keeping only the items that have not expired
+            rose.retain(|item| !item.is_expired());
+
+            // THEN the rest should be left in order
+            assert_eq!(vec![
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(COMMON_ITEM, 4, 7),
+            ], rose.items);
+        }
+
+        #[test]
+        fn test_retain_by_name_after_updates() {
+            // GIVEN a shop that has been updated
+            let mut rose = inventory();
+            rose.update_quality();
+
+            // WHEN removing an item by name and updating again
+            rose.retain(|item| item.name != AGED_BRIE_ITEM);
+            rose.update_quality();
+
+            // THEN the remaining items should have been updated as usual
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, -3, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, -5, 0),
+                Item::new(COMMON_ITEM, 2, 5),
+            ], rose.items);
+        }
+    }
+
+    mod sell_in_range_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_sell_in_range() {
+            // GIVEN a shop with legendary items outside the range of the others
+            let rose = GildedRose::new(vec![
+                Item::new(LEGENDARY_ITEM, -1, 80),
+                Item::new(COMMON_ITEM, 4, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(LEGENDARY_ITEM, 30, 80),
+            ]);
+
+            // WHEN asking for the range with and without legendary items
+            // THEN legendary items should only count when included
+            assert_eq!(Some(2), rose.min_sell_in(false));
+            assert_eq!(Some(4), rose.max_sell_in(false));
+            assert_eq!(Some(-1), rose.min_sell_in(true));
+            assert_eq!(Some(30), rose.max_sell_in(true));
+        }
+
+        #[test]
+        fn test_no_sell_in_range() {
+            let legendary_only = GildedRose::new(vec![Item::new(LEGENDARY_ITEM, 0, 80)]);
+
+            assert_eq!(None, GildedRose::default().min_sell_in(true));
+            assert_eq!(None, GildedRose::default().max_sell_in(true));
+            assert_eq!(None, legendary_only.min_sell_in(false));
+            assert_eq!(None, legendary_only.max_sell_in(false));
+            assert_eq!(Some(0), legendary_only.max_sell_in(true));
+        }
+    }
+
+    mod index_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ])
+        }
+
+        #[test]
+        fn test_index() {
+            let mut rose = inventory();
+
+            rose.update_quality();
+
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 1, 1), rose[1]);
+            assert_eq!(COMMON_ITEM, rose[0].name);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_index_out_of_range() {
+            let rose = inventory();
+
+            let _ = &rose[2];
+        }
+    }
+
+    mod random_inventory_feature {
+        use super::GildedRose;
+
+        #[test]
+        fn test_same_seed_same_inventory() {
+            // GIVEN two random inventories from the same seed
+            let first = GildedRose::random(500, 42);
+            let second = GildedRose::random(500, 42);
+
+            // THEN they should be identical and valid
+            assert_eq!(500, first.len());
+            assert_eq!(first.items, second.items);
+            assert!(first.validate().is_empty());
+        }
+
+        #[test]
+        fn test_different_seeds_differ() {
+            let first = GildedRose::random(100, 1);
+            let second = GildedRose::random(100, 2);
+
+            assert_ne!(first.items, second.items);
+            assert!(GildedRose::random(0, 1).is_empty());
+        }
+    }
+
+    mod collect_expired_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_items_collected_on_expiry_day() {
+            // GIVEN items expiring at different times, one already expired and one that never does
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 2, 10),
+                Item::new(AGED_BRIE_ITEM, 0, 10),
+                Item::new(BACKSTAGE_PASSES_ITEM, -1, 0),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(BACKSTAGE_PASSES_ITEM, 10, 10),
+            ]);
+
+            // WHEN advancing five days
+            let expired = rose.advance_collecting_expired(5);
+
+            // THEN each newly expired item should be collected once, on the day it expired
+            assert_eq!(vec![
+                (1, Item::new(AGED_BRIE_ITEM, -1, 12)),
+                (3, Item::new(COMMON_ITEM, -1, 6)),
+            ], expired);
+            assert_eq!(Item::new(COMMON_ITEM, -3, 2), rose[0]);
+        }
+    }
+
+    mod frozen_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_frozen_item_waits_until_unfrozen() {
+            // GIVEN a frozen common item
+            let mut rose = GildedRose::new(vec![
+                Item { frozen: true, ..Item::new(COMMON_ITEM, 1, 10) },
+                Item::new(COMMON_ITEM, 1, 10),
+            ]);
+
+            // WHEN updating quality while it is frozen
+            rose.update_quality_days(3);
+
+            // THEN it should not change while the other item decays
+            assert_eq!(Item { frozen: true, ..Item::new(COMMON_ITEM, 1, 10) }, rose[0]);
+            assert_eq!(Item::new(COMMON_ITEM, -2, 5), rose[1]);
+
+            // ...and WHEN unfreezing it and updating again
+            rose.get_mut(0).unwrap().frozen = false;
+            rose.update_quality_days(2);
+
+            // THEN it should decay as usual
+            assert_eq!(Item::new(COMMON_ITEM, -1, 7), rose[0]);
+        }
+    }
+
+    mod equality_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_equal_inventories() {
+            // GIVEN a shop that has been updated and one constructed in its final state
+            let mut updated = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+            updated.update_quality();
+            let expected = GildedRose::from_tuples(vec![(COMMON_ITEM, 4, 6), (AGED_BRIE_ITEM, 1, 1)]);
+
+            // THEN they should compare equal
+            assert_eq!(expected, updated);
+        }
+
+        #[test]
+        fn test_different_inventories() {
+            let rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 5, 7)]);
+
+            assert_ne!(GildedRose::new(vec![Item::new(COMMON_ITEM, 5, 6)]), rose);
+            assert_ne!(GildedRose::default(), rose);
+            assert_ne!(GildedRose::with_bounds(rose.items.clone(), 0, 80), rose);
+        }
+    }
+
+    mod middleware_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_middleware_floors_quality() {
+            // GIVEN a shop whose middleware forbids quality dropping below 5
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 2, 8),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+            rose.set_middleware(Box::new(|item: &Item, quality: i32, sell_in: i32| {
+                (quality.max(5.min(item.quality)), sell_in)
+            }));
+
+            // WHEN updating quality for a week
+            rose.update_quality_days(7);
+
+            // THEN the common item should stop at 5 instead of 0, and brie should be unaffected
+            assert_eq!(Item::new(COMMON_ITEM, -5, 5), rose[0]);
+            assert_eq!(Item::new(AGED_BRIE_ITEM, -5, 12), rose[1]);
+        }
+
+        #[test]
+        fn test_middleware_skips_frozen_and_legendary_items() {
+            // GIVEN a frozen item and a legendary item under a middleware that raises quality to
+            // at least 5 and moves sell_in
+            let mut rose = GildedRose::new(vec![
+                Item { frozen: true, ..Item::new(COMMON_ITEM, 2, 2) },
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(COMMON_ITEM, 2, 2),
+            ]);
+            rose.set_middleware(Box::new(|_: &Item, quality: i32, sell_in: i32| (quality.max(5), sell_in - 1)));
 
             // WHEN updating quality
             rose.update_quality();
 
-            // THEN quality should degrade by 2
-            assert_eq!(2, rose.items[0].sell_in);
-            assert_eq!(16, rose.items[0].quality);
+            // THEN neither should change, while other items still go through the middleware
+            assert_eq!(Item { frozen: true, ..Item::new(COMMON_ITEM, 2, 2) }, rose[0]);
+            assert_eq!(Item::new(LEGENDARY_ITEM, 0, 80), rose[1]);
+            assert_eq!(Item::new(COMMON_ITEM, 0, 5), rose[2]);
+        }
 
-            // ...and WHEN updating quality 2 more times
-            for _ in 1..=2 {
+        #[test]
+        fn test_middleware_can_veto() {
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 2, 8)]);
+            rose.set_middleware(Box::new(|item: &Item, _, _| (item.quality, item.sell_in)));
+
+            rose.update_quality();
+
+            assert_eq!(Item::new(COMMON_ITEM, 2, 8), rose[0]);
+        }
+    }
+
+    mod sell_in_histogram_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 0, 7),
+                Item::new(COMMON_ITEM, 4, 7),
+                Item::new(AGED_BRIE_ITEM, -1, 10),
+                Item::new(AGED_BRIE_ITEM, -5, 10),
+                Item::new(BACKSTAGE_PASSES_ITEM, 12, 20),
+                Item::new(LEGENDARY_ITEM, 3, 80),
+            ])
+        }
+
+        #[test]
+        fn test_histogram_buckets() {
+            // GIVEN items across several sell_in ranges
+            let rose = inventory();
+
+            // WHEN counting them in buckets of five days
+            let histogram = rose.sell_in_histogram(5, false);
+            let with_legendary = rose.sell_in_histogram(5, true);
+
+            // THEN each bucket should be keyed by its lowest sell_in
+            let expected = vec![(-5, 2), (0, 2), (10, 1)];
+            assert_eq!(expected, histogram.into_iter().collect::<Vec<_>>());
+            assert_eq!(3, with_legendary[&0]);
+        }
+
+        #[test]
+        #[should_panic(expected = "bucket size 0 is not positive")]
+        fn test_zero_bucket_size() {
+            inventory().sell_in_histogram(0, true);
+        }
+    }
+
+    mod update_slice_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, update_slice};
+
+        #[test]
+        fn test_slice_matches_owned_inventory() {
+            // GIVEN items in a stack array and the same items in a shop
+            let mut items = [
+                Item::new(COMMON_ITEM, 1, 7),
+                Item::new(CONJURED_ITEM, 3, 6),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 49),
+                Item::new(BACKSTAGE_PASSES_ITEM, 6, 20),
+            ];
+            let mut rose = GildedRose::new(items.to_vec());
+
+            // WHEN updating both for a few days
+            for _ in 0..4 {
+                update_slice(&mut items, 0, 50);
                 rose.update_quality();
             }
 
-            // THEN quality should degrade twice by 2 for a total of 4
-            assert_eq!(0, rose.items[0].sell_in);
-            assert_eq!(12, rose.items[0].quality);
+            // THEN they should end up the same
+            assert_eq!(rose.items, items.to_vec());
+        }
+    }
 
-            // ...and WHEN updating quality 1 more time
+    mod days_to_quality_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::Item;
+
+        #[test]
+        fn test_brie_reaches_max() {
+            // GIVEN aged brie two days before its last day of sale
+            let brie = Item::new(AGED_BRIE_ITEM, 2, 40);
+
+            // WHEN asking how long it takes to reach 50
+            // THEN it should gain 1 for two days and 2 a day after that
+            assert_eq!(Some(6), brie.days_to_quality(50, 0, 50));
+            assert_eq!(Some(0), brie.days_to_quality(40, 0, 50));
+        }
+
+        #[test]
+        fn test_common_item_never_gains() {
+            let item = Item::new(COMMON_ITEM, 5, 7);
+
+            assert_eq!(None, item.days_to_quality(8, 0, 50));
+        }
+
+        #[test]
+        fn test_pass_zeroes_after_concert() {
+            // GIVEN a backstage pass three days before the concert
+            let pass = Item::new(BACKSTAGE_PASSES_ITEM, 3, 30);
+
+            // THEN it should reach what it gains before the concert, but nothing beyond
+            assert_eq!(Some(3), pass.days_to_quality(39, 0, 50));
+            assert_eq!(None, pass.days_to_quality(40, 0, 50));
+        }
+    }
+
+    mod try_update_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item, ItemValidation, UpdateError, ValidationReason};
+
+        #[test]
+        fn test_valid_inventory_updates() {
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+            ]);
+
+            assert_eq!(Ok(()), rose.try_update_quality());
+            assert_eq!(vec![
+                Item::new(COMMON_ITEM, 4, 6),
+                Item::new(AGED_BRIE_ITEM, 1, 1),
+            ], rose.items);
+        }
+
+        #[test]
+        fn test_invalid_inventory_is_left_alone() {
+            // GIVEN an inventory with an item of quality 99
+            let items = vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 99),
+            ];
+            let mut rose = GildedRose::new(items.clone());
+
+            // WHEN trying to update it
+            let result = rose.try_update_quality();
+
+            // THEN the offending item should be reported and nothing should change
+            let validation = ItemValidation { index: 1, reason: ValidationReason::QualityOutOfRange { quality: 99 } };
+            assert_eq!(Err(UpdateError { validations: vec![validation] }), result);
+            assert_eq!("invalid item at index 1", result.unwrap_err().to_string());
+            assert_eq!(items, rose.items);
+        }
+    }
+
+    mod clamp_policy_feature {
+        use gildedrose::tests::{COMMON_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM};
+        use super::{ClampPolicy, GildedRose, Item};
+
+        fn inventory() -> Vec<Item> {
+            vec![
+                Item::new(COMMON_ITEM, 0, 1),
+                Item::new(AGED_BRIE_ITEM, -1, 49),
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ]
+        }
+
+        #[test]
+        fn test_policies_at_the_bounds() {
+            // GIVEN shops with each policy and items about to pass a bound
+            let mut saturating = GildedRose::new(inventory());
+            let mut rejecting = GildedRose::new(inventory()).with_clamp_policy(ClampPolicy::Reject);
+            let mut resetting = GildedRose::new(inventory()).with_clamp_policy(ClampPolicy::Reset(0));
+
+            // WHEN updating quality
+            saturating.update_quality();
+            rejecting.update_quality();
+            resetting.update_quality();
+
+            // THEN only the items that passed a bound should differ
+            assert_eq!(vec![0, 50, 6, 80], saturating.items.iter().map(|item| item.quality).collect::<Vec<_>>());
+            assert_eq!(vec![1, 49, 6, 80], rejecting.items.iter().map(|item| item.quality).collect::<Vec<_>>());
+            assert_eq!(vec![0, 0, 6, 80], resetting.items.iter().map(|item| item.quality).collect::<Vec<_>>());
+            assert_eq!(-1, rejecting[0].sell_in);
+        }
+
+        #[test]
+        #[should_panic(expected = "reset quality 100 is outside 0..=50")]
+        fn test_reset_above_bounds() {
+            GildedRose::new(inventory()).with_clamp_policy(ClampPolicy::Reset(100));
+        }
+
+        #[test]
+        #[should_panic(expected = "reset quality -5 is outside 0..=50")]
+        fn test_reset_below_bounds() {
+            GildedRose::new(inventory()).with_clamp_policy(ClampPolicy::Reset(-5));
+        }
+    }
+
+    mod items_iter_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::GildedRose;
+
+        #[test]
+        fn test_iterate_both_ways() {
+            let rose = GildedRose::from_tuples(vec![(COMMON_ITEM, 5, 7), (AGED_BRIE_ITEM, 2, 0), (BACKSTAGE_PASSES_ITEM, 15, 20)]);
+
+            let forward: Vec<&str> = rose.items_iter().map(|item| item.name.as_str()).collect();
+            let backward: Vec<&str> = rose.items_iter().rev().map(|item| item.name.as_str()).collect();
+
+            assert_eq!(vec![COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM], forward);
+            assert_eq!(vec![BACKSTAGE_PASSES_ITEM, AGED_BRIE_ITEM, COMMON_ITEM], backward);
+            assert_eq!(3, rose.items_iter().len());
+            assert_eq!(0, GildedRose::default().items_iter().len());
+        }
+    }
+
+    mod table_feature {
+        use gildedrose::tests::{COMMON_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_aligned_table() {
+            // GIVEN a shop with a long name and numbers of different widths
+            let rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(BACKSTAGE_PASSES_ITEM, -15, 20),
+            ]);
+
+            // WHEN writing it as a table
+            let table = rose.to_table();
+
+            // THEN every column should be as wide as its widest value
+            assert_eq!("\
+Name                                      | Sell In | Quality
+------------------------------------------+---------+--------
+Elixir of the Mongoose                    |       5 |       7
+Backstage passes to a TAFKAL80ETC concert |     -15 |      20", table);
+        }
+
+        #[test]
+        fn test_empty_table_has_headers() {
+            let table = GildedRose::default().to_table();
+
+            assert_eq!("Name | Sell In | Quality\n-----+---------+--------", table);
+        }
+    }
+
+    mod decay_tiers_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item, DefaultStrategy};
+
+        #[test]
+        fn test_three_decay_tiers() {
+            // GIVEN a perishable that loses 3 a day once it is 5 days past its sell by date
+            let mut rose = GildedRose::new(vec![
+                Item::new("Fresh Fish", 1, 40),
+            ]);
+            rose.register_strategy("Fresh Fish", Box::new(DefaultStrategy::with_decay_tiers(vec![(-5, 3), (0, 2)])));
+
+            // WHEN updating quality day by day
+            let losses: Vec<i32> = (0..9).map(|_| {
+                let quality = rose[0].quality;
+                rose.update_quality();
+                quality - rose[0].quality
+            }).collect();
+
+            // THEN it should lose 1 at sell_in 1, 2 from sell_in 0 and 3 from sell_in -5
+            assert_eq!(vec![1, 2, 2, 2, 2, 2, 3, 3, 3], losses);
+        }
+
+        #[test]
+        fn test_default_tiers_unchanged() {
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 1, 40)]);
+
+            rose.update_quality_days(8);
+
+            assert_eq!(Item::new(COMMON_ITEM, -7, 25), rose[0]);
+        }
+    }
+
+    mod strategy_override_feature {
+        use gildedrose::tests::{COMMON_ITEM};
+        use super::{GildedRose, Item, LegendaryStrategy};
+
+        #[test]
+        fn test_override_freezes_one_item() {
+            // GIVEN three identical common items, the last overridden with a legendary strategy
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, 5, 10),
+            ]);
+            rose.apply_strategy_override(2, Box::new(LegendaryStrategy));
+
+            // WHEN updating quality
+            rose.update_quality_days(2);
+
+            // THEN only the overridden item should stay put
+            assert_eq!(Item::new(COMMON_ITEM, 3, 8), rose[1]);
+            assert_eq!(Item::new(COMMON_ITEM, 5, 10), rose[2]);
+
+            // ...and WHEN removing the override and updating again
+            assert!(rose.remove_strategy_override(2).is_some());
             rose.update_quality();
 
-            // THEN quality should degrade twice as fast, so by 4
-            assert_eq!(-1, rose.items[0].sell_in);
-            assert_eq!(8, rose.items[0].quality);
+            // THEN it should degrade like its neighbours
+            assert_eq!(Item::new(COMMON_ITEM, 4, 9), rose[2]);
+            assert_eq!(Item::new(COMMON_ITEM, 2, 7), rose[1]);
+        }
+
+        #[test]
+        fn test_overrides_leave_with_their_items() {
+            // GIVEN an override on every item
+            let mut rose = GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 10),
+                Item::new(COMMON_ITEM, -1, 10),
+                Item::new(COMMON_ITEM, 3, 10),
+                Item::new(COMMON_ITEM, 3, 10),
+            ]);
+            for index in 0..rose.len() {
+                rose.apply_strategy_override(index, Box::new(LegendaryStrategy));
+            }
+
+            // WHEN removing items in different ways
+            rose.remove(0);
+            rose.dedup();
+            assert_eq!(2, rose.overrides.len());
+            let (mut fresh, _) = rose.partition_expired();
+
+            // THEN only the overrides of the remaining items should be kept
+            assert_eq!(1, fresh.overrides.len());
+            fresh.update_quality();
+            assert_eq!(Item::new(COMMON_ITEM, 3, 10), fresh[0]);
+
+            // ...and restoring a snapshot from before an item was pushed should drop its override
+            let snapshot = fresh.snapshot();
+            fresh.push(Item::new(COMMON_ITEM, 1, 10));
+            fresh.apply_strategy_override(1, Box::new(LegendaryStrategy));
+            fresh.restore(snapshot);
+            assert_eq!(1, fresh.overrides.len());
+
+            // ...and clearing should drop them all
+            fresh.clear();
+            assert!(fresh.overrides.is_empty());
+        }
+    }
+
+    mod item_id_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, LegendaryStrategy};
+
+        fn inventory() -> GildedRose {
+            GildedRose::new(vec![
+                Item::new(COMMON_ITEM, 5, 7),
+                Item::new(AGED_BRIE_ITEM, 2, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, 15, 20),
+            ])
+        }
+
+        #[test]
+        fn test_id_survives_removal() {
+            // GIVEN a shop and the id of its last item
+            let mut rose = inventory();
+            let id = rose.id_of(2).unwrap();
+
+            // WHEN removing an earlier item
+            rose.remove(0);
+
+            // THEN the item should keep its id and be found by it
+            assert_eq!(Some(id), rose.id_of(1));
+            assert_eq!(Some(&Item::new(BACKSTAGE_PASSES_ITEM, 15, 20)), rose.find_by_id(id));
+            assert_eq!(None, rose.find_by_id(rose.len() as u64 + 10));
+        }
+
+        #[test]
+        fn test_ids_follow_reordering() {
+            // GIVEN a shop with a pushed item
+            let mut rose = inventory();
+            rose.push(Item::new(COMMON_ITEM, 1, 40));
+            let ids: Vec<u64> = (0..4).map(|index| rose.id_of(index).unwrap()).collect();
+
+            // WHEN sorting and filtering the items
+            rose.sort_by_quality();
+            rose.retain(|item| item.name != AGED_BRIE_ITEM);
+
+            // THEN every item should keep its own id, and ids should not repeat
+            assert_eq!(Some(&Item::new(COMMON_ITEM, 1, 40)), rose.find_by_id(ids[3]));
+            assert_eq!(Some(&Item::new(COMMON_ITEM, 5, 7)), rose.find_by_id(ids[0]));
+            assert_eq!(None, rose.find_by_id(ids[1]));
+            assert_eq!(4, ids.iter().collect::<std::collections::BTreeSet<_>>().len());
+        }
+
+        #[test]
+        fn test_id_survives_changes_in_place() {
+            // GIVEN a shop and the id of its first item
+            let mut rose = inventory();
+            let id = rose.id_of(0).unwrap();
+
+            // WHEN renaming the item in place and sorting the items
+            rose.get_mut(0).unwrap().name = "Elixir of the Owl".to_string();
+            rose.sort_by_quality();
+
+            // THEN the renamed item should keep its id wherever it ended up
+            assert_eq!("Elixir of the Owl", rose.find_by_id(id).unwrap().name);
+            assert_eq!(rose.len(), (0..rose.len()).filter_map(|index| rose.id_of(index)).count());
+        }
+
+        #[test]
+        fn test_ids_of_items_changed_directly() {
+            // GIVEN a shop with an item pushed onto its items directly
+            let mut rose = inventory();
+            let first_id = rose.id_of(0).unwrap();
+            rose.items.push(Item::new(COMMON_ITEM, 1, 40));
+            assert_eq!(None, rose.id_of(3));
+
+            // WHEN the shop next changes its items
+            rose.update_quality();
+
+            // THEN the pushed item should get the next id
+            assert_eq!(Some(3), rose.id_of(3));
+
+            // ...but removing an item directly should leave the ids in place, going by position
+            rose.items.remove(0);
+            rose.update_quality();
+            assert_eq!(Some(first_id), rose.id_of(0));
+            assert_eq!(None, rose.id_of(3));
+        }
+
+        #[test]
+        fn test_override_follows_item() {
+            // GIVEN a frozen override on the last item
+            let mut rose = inventory();
+            rose.apply_strategy_override(2, Box::new(LegendaryStrategy));
+
+            // WHEN removing an earlier item and updating
+            rose.remove(0);
+            rose.update_quality();
+
+            // THEN the override should still apply to the same item
+            assert_eq!(Item::new(AGED_BRIE_ITEM, 1, 1), rose[0]);
+            assert_eq!(Item::new(BACKSTAGE_PASSES_ITEM, 15, 20), rose[1]);
+        }
+    }
+
+    mod stable_inventory_feature {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, ItemChange, LegendaryStrategy};
+
+        #[test]
+        fn test_stable_inventory_returns_immediately() {
+            // GIVEN an inventory that has reached a fixed point
+            let items = vec![
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(COMMON_ITEM, -10, 0),
+                Item::new(BACKSTAGE_PASSES_ITEM, -10, 0),
+                Item { frozen: true, ..Item::new(COMMON_ITEM, 5, 7) },
+            ];
+            let mut rose = GildedRose::new(items.clone()).with_sell_in_floor(Some(-10));
+
+            // WHEN updating it for as many days as possible
+            rose.update_quality_days(u32::MAX);
+
+            // THEN it should return without changing anything
+            assert_eq!(items, rose.items);
+        }
+
+        #[test]
+        fn test_inventory_reaching_fixed_point() {
+            // GIVEN an item that will stop changing once it reaches the sell_in floor
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 2, 7)]).with_sell_in_floor(Some(-3));
+
+            // WHEN updating it for as many days as possible
+            rose.tick_days(u32::MAX);
+
+            // THEN it should end up at the floor with no quality left
+            assert_eq!(Item::new(COMMON_ITEM, -3, 0), rose[0]);
+        }
+
+        #[test]
+        fn test_settled_inventory_without_sell_in_floor() {
+            // GIVEN items of every kind that keep counting down sell_in, and no sell_in floor
+            let items = vec![
+                Item::new(COMMON_ITEM, 5, 3),
+                Item::new(CONJURED_ITEM, 3, 4),
+                Item::new(AGED_BRIE_ITEM, 1, 48),
+                Item::new(BACKSTAGE_PASSES_ITEM, 2, 10),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+            ];
+            let mut rose = GildedRose::new(items.clone());
+            let mut stepped = GildedRose::new(items);
+
+            // WHEN updating one shop for 40 days at once and the other one day at a time
+            rose.update_quality_days(40);
+            for _ in 0..40 {
+                stepped.update_quality();
+            }
+
+            // THEN both should end up the same, even though sell_in never stops changing
+            assert_eq!(stepped.items, rose.items);
+
+            // ...and WHEN updating for as many days as possible
+            rose.update_quality_days(u32::MAX);
+
+            // THEN it should return with sell_in counted down as far as it goes
+            assert_eq!(Item::new(COMMON_ITEM, i32::MIN, 0), rose[0]);
+            assert_eq!(Item::new(AGED_BRIE_ITEM, i32::MIN, 50), rose[2]);
+            assert_eq!(Item::new(LEGENDARY_ITEM, 0, 80), rose[4]);
+        }
+
+        #[test]
+        fn test_custom_strategy_keeps_running() {
+            // GIVEN an expired item whose registered strategy only starts gaining below sell_in -3
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, -1, 0)]);
+            rose.register_strategy(COMMON_ITEM, Box::new(|item: &Item| {
+                if item.sell_in < -3 { item.quality + 1 } else { item.quality }
+            }));
+
+            // WHEN updating it for days that keep its quality before days that change it
+            rose.update_quality_days(5);
+
+            // THEN the days keeping its quality should not be taken as settled
+            assert_eq!(Item::new(COMMON_ITEM, -6, 2), rose[0]);
+        }
+
+        #[test]
+        fn test_still_items_next_to_settled_items() {
+            // GIVEN a settled item next to an overridden item and a registered one that stay put
+            let items = vec![
+                Item::new("Elixir", -5, 0),
+                Item::new("Elixir", 7, 10),
+                Item::new("Excalibur", 3, 10),
+            ];
+            let shop = || {
+                let mut rose = GildedRose::new(items.clone());
+                rose.apply_strategy_override(1, Box::new(LegendaryStrategy));
+                rose.register_strategy("Excalibur", Box::new(LegendaryStrategy));
+                rose
+            };
+            let mut rose = shop();
+            let mut stepped = shop();
+
+            // WHEN updating one shop for 10 days at once and the other one day at a time
+            rose.update_quality_days(10);
+            for _ in 0..10 {
+                stepped.update_quality();
+            }
+
+            // THEN only the settled item should have counted down its sell_in
+            assert_eq!(stepped.items, rose.items);
+            assert_eq!(vec![Item::new("Elixir", -15, 0), items[1].clone(), items[2].clone()], rose.items);
+        }
+
+        #[test]
+        fn test_callback_sees_every_settled_day() {
+            // GIVEN a settled item and a callback counting the changes
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, -5, 0)]);
+            let count = Rc::new(Cell::new(0));
+            let counter = Rc::clone(&count);
+            rose.on_update(Box::new(move |_: &ItemChange| counter.set(counter.get() + 1)));
+
+            // WHEN updating it for 10 days
+            rose.update_quality_days(10);
+
+            // THEN the callback should have seen the change of every day
+            assert_eq!(10, count.get());
+            assert_eq!(Item::new(COMMON_ITEM, -15, 0), rose[0]);
+        }
+    }
+
+    mod item_setters_feature {
+        use gildedrose::tests::{COMMON_ITEM, AGED_BRIE_ITEM};
+        use super::{GildedRose, Item};
+
+        #[test]
+        fn test_chained_setters() {
+            // GIVEN a common item
+            let mut item = Item::new(COMMON_ITEM, 5, 7);
+
+            // WHEN changing it with all three setters in one chain
+            item.with_name(AGED_BRIE_ITEM).with_sell_in(-1).with_quality(10);
+
+            // THEN it should have every change
+            assert_eq!(Item::new(AGED_BRIE_ITEM, -1, 10), item);
+        }
+
+        #[test]
+        fn test_setters_on_shop_items() {
+            let mut rose = GildedRose::new(vec![Item::new(COMMON_ITEM, 5, 7)]);
+
+            rose.items[0].with_quality(20).with_sell_in(0);
+            rose.update_quality();
+
+            assert_eq!(Item::new(COMMON_ITEM, -1, 18), rose[0]);
+        }
+    }
+
+    mod peak_strategy_feature {
+        use super::{GildedRose, Item, PeakStrategy};
+
+        const TRUFFLE: &str = "White Truffle";
+
+        #[test]
+        fn test_turnaround_at_peak() {
+            // GIVEN a truffle that peaks at sell_in 3
+            let mut rose = GildedRose::new(vec![
+                Item::new(TRUFFLE, 5, 20),
+            ]);
+            rose.register_strategy(TRUFFLE, Box::new(PeakStrategy::new(3)));
+
+            // WHEN updating quality until it has reached its peak
+            rose.update_quality_days(2);
+
+            // THEN it should have gained 1 a day
+            assert_eq!(Item::new(TRUFFLE, 3, 22), rose[0]);
+
+            // ...and WHEN updating quality past its peak
+            rose.update_quality_days(3);
+
+            // THEN it should lose 1 a day
+            assert_eq!(Item::new(TRUFFLE, 0, 19), rose[0]);
         }
     }
 
+    /// Runs with `cargo test --no-default-features`, checking that the core update logic builds
+    /// and works with only `alloc`.
+    #[cfg(not(feature = "std"))]
+    mod no_std_feature {
+        use gildedrose::tests::{COMMON_ITEM, CONJURED_ITEM, LEGENDARY_ITEM, AGED_BRIE_ITEM, BACKSTAGE_PASSES_ITEM};
+        use super::{GildedRose, Item, update_item, update_slice};
+
+        #[test]
+        fn test_core_updates_without_std() {
+            // GIVEN items of every kind, in a slice, one by one and in a shop
+            let mut items = [
+                Item::new(COMMON_ITEM, 1, 7),
+                Item::new(CONJURED_ITEM, 3, 6),
+                Item::new(LEGENDARY_ITEM, 0, 80),
+                Item::new(AGED_BRIE_ITEM, 2, 49),
+                Item::new(BACKSTAGE_PASSES_ITEM, 6, 20),
+            ];
+            let mut single = items.clone();
+            let mut rose = GildedRose::new(items.to_vec());
+
+            // WHEN updating them all for two days without the std feature
+            for _ in 0..2 {
+                update_slice(&mut items, 0, 50);
+                for item in single.iter_mut() {
+                    update_item(item, 0, 50);
+                }
+                rose.update_quality();
+            }
+
+            // THEN they should all end up the same
+            assert_eq!(Item::new(COMMON_ITEM, -1, 4), items[0]);
+            assert_eq!(Item::new(BACKSTAGE_PASSES_ITEM, 4, 25), items[4]);
+            assert_eq!(items, single);
+            assert_eq!(rose.items, items.to_vec());
+        }
+    }
 }
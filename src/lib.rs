@@ -0,0 +1,31 @@
+//! The Gilded Rose inventory. The update logic only needs `alloc`; building without the default
+//! `std` feature makes the crate `no_std`, leaving out CSV loading and the `HashMap` reports.
+//! `cargo build --no-default-features` checks that it builds that way, and
+//! `cargo test --no-default-features` also runs a test of the update logic without `std`.
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(test)]
+extern crate proptest;
+
+#[cfg(feature = "std")]
+mod csv;
+mod gildedrose;
+#[cfg(feature = "std")]
+mod load;
+
+#[cfg(feature = "std")]
+pub use csv::CsvError;
+pub use gildedrose::*;
+#[cfg(feature = "std")]
+pub use load::{Format, LoadError};
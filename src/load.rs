@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{self, Read};
+
+use csv::CsvError;
+use gildedrose::GildedRose;
+
+
+/// The format of an inventory read by `GildedRose::from_reader`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// JSON when the first character other than whitespace is `[` or `{`, CSV otherwise.
+    Auto,
+    /// A JSON array, as read by `GildedRose::from_json`. Needs the `serde` feature.
+    Json,
+    /// CSV, as read by `GildedRose::from_csv`.
+    Csv,
+}
+
+/// Why `GildedRose::from_reader` could not load an inventory.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Csv(CsvError),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// The inventory is JSON but the crate was built without the `serde` feature.
+    #[cfg(not(feature = "serde"))]
+    JsonUnsupported,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "could not read inventory: {}", error),
+            LoadError::Csv(error) => write!(f, "{}", error),
+            #[cfg(feature = "serde")]
+            LoadError::Json(error) => write!(f, "invalid JSON: {}", error),
+            #[cfg(not(feature = "serde"))]
+            LoadError::JsonUnsupported => write!(f, "JSON support needs the `serde` feature"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            LoadError::Csv(error) => Some(error),
+            #[cfg(feature = "serde")]
+            LoadError::Json(error) => Some(error),
+            #[cfg(not(feature = "serde"))]
+            LoadError::JsonUnsupported => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> LoadError {
+        LoadError::Io(error)
+    }
+}
+
+impl From<CsvError> for LoadError {
+    fn from(error: CsvError) -> LoadError {
+        LoadError::Csv(error)
+    }
+}
+
+
+impl GildedRose {
+    /// Loads a shop from an inventory in `format`, working out whether it is JSON or CSV from
+    /// its first character for `Format::Auto`.
+    pub fn from_reader(mut reader: impl Read, format: Format) -> Result<GildedRose, LoadError> {
+        let mut inventory = String::new();
+        reader.read_to_string(&mut inventory)?;
+
+        let format = match format {
+            Format::Auto => sniff(&inventory),
+            format => format,
+        };
+        match format {
+            Format::Json => from_json(&inventory),
+            _ => Ok(GildedRose::from_csv(inventory.as_bytes())?),
+        }
+    }
+}
+
+fn sniff(inventory: &str) -> Format {
+    match inventory.trim_start().chars().next() {
+        Some('[') | Some('{') => Format::Json,
+        _ => Format::Csv,
+    }
+}
+
+#[cfg(feature = "serde")]
+fn from_json(inventory: &str) -> Result<GildedRose, LoadError> {
+    GildedRose::from_json(inventory).map_err(LoadError::Json)
+}
+
+#[cfg(not(feature = "serde"))]
+fn from_json(_inventory: &str) -> Result<GildedRose, LoadError> {
+    Err(LoadError::JsonUnsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use gildedrose::{GildedRose, Item, AGED_BRIE_ITEM, COMMON_ITEM};
+    use super::{Format, LoadError};
+
+    const CSV: &str = "name,sell_in,quality\n\
+                       Elixir of the Mongoose,5,7\n\
+                       Aged Brie,2,0\n";
+    const JSON: &str = r#"
+        [{ "name": "Elixir of the Mongoose", "sell_in": 5, "quality": 7 },
+         { "name": "Aged Brie", "sell_in": 2, "quality": 0 }]"#;
+
+    fn expected_items() -> Vec<Item> {
+        vec![
+            Item::new(COMMON_ITEM, 5, 7),
+            Item::new(AGED_BRIE_ITEM, 2, 0),
+        ]
+    }
+
+    #[test]
+    fn test_auto_detects_csv() {
+        let rose = GildedRose::from_reader(CSV.as_bytes(), Format::Auto).unwrap();
+
+        assert_eq!(expected_items(), rose.items);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_auto_detects_json() {
+        // GIVEN a JSON array with leading whitespace
+        // WHEN loading it without naming the format
+        let rose = GildedRose::from_reader(JSON.as_bytes(), Format::Auto).unwrap();
+
+        // THEN it should be read as JSON
+        assert_eq!(expected_items(), rose.items);
+    }
+
+    #[cfg(not(feature = "serde"))]
+    #[test]
+    fn test_json_needs_serde() {
+        match GildedRose::from_reader(JSON.as_bytes(), Format::Auto) {
+            Err(LoadError::JsonUnsupported) => {},
+            _ => panic!("expected JSON to be unsupported"),
+        }
+    }
+
+    #[test]
+    fn test_explicit_format_mismatch() {
+        match GildedRose::from_reader(JSON.as_bytes(), Format::Csv) {
+            Err(LoadError::Csv(_)) => {},
+            _ => panic!("expected a CSV error"),
+        }
+        #[cfg(feature = "serde")]
+        match GildedRose::from_reader(CSV.as_bytes(), Format::Json) {
+            Err(LoadError::Json(_)) => {},
+            _ => panic!("expected a JSON error"),
+        }
+    }
+}
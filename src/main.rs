@@ -1,8 +1,118 @@
-mod gildedrose;
+extern crate gildedrose;
+
+use std::env;
+use std::fs::File;
+use std::process;
 
 use gildedrose::{GildedRose, Item};
 
+const USAGE: &str = "usage: gildedrose [--input <path>] [--days <n>] [--format json|csv|text]";
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+    Text,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Format, String> {
+        match format {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "text" => Ok(Format::Text),
+            _ => Err(format!("unknown format `{}`, expected json, csv or text", format)),
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        demo();
+        return;
+    }
+
+    match run(&args) {
+        Ok(output) => println!("{}", output),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(1);
+        },
+    }
+}
+
+/// Loads the inventory named by `--input`, updates it `--days` times and returns it written in
+/// `--format`. JSON inventories are recognised by a `.json` extension, anything else is read
+/// as CSV.
+fn run(args: &[String]) -> Result<String, String> {
+    let mut input = None;
+    let mut days = 1;
+    let mut format = Format::Text;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for `{}`\n{}", arg, USAGE));
+        match arg.as_str() {
+            "--input" => input = Some(value()?),
+            "--days" => {
+                let days_arg = value()?;
+                days = days_arg.parse().map_err(|_| format!("`{}` is not a number of days", days_arg))?;
+            },
+            "--format" => format = Format::parse(value()?)?,
+            _ => return Err(format!("unknown argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    let input = input.ok_or(format!("missing `--input`\n{}", USAGE))?;
+    let mut rose = load(input)?;
+    rose.update_quality_days(days);
+    write(&rose, format)
+}
+
+fn load(path: &str) -> Result<GildedRose, String> {
+    let file = File::open(path).map_err(|error| format!("could not open `{}`: {}", path, error))?;
+    if path.ends_with(".json") {
+        load_json(file, path)
+    } else {
+        GildedRose::from_csv(file).map_err(|error| format!("`{}`: {}", path, error))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load_json(mut file: File, path: &str) -> Result<GildedRose, String> {
+    use std::io::Read;
+
+    let mut json = String::new();
+    file.read_to_string(&mut json).map_err(|error| format!("could not read `{}`: {}", path, error))?;
+    GildedRose::from_json(&json).map_err(|error| format!("`{}`: {}", path, error))
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_json(_file: File, path: &str) -> Result<GildedRose, String> {
+    Err(format!("cannot read `{}`: JSON support needs the `serde` feature", path))
+}
+
+fn write(rose: &GildedRose, format: Format) -> Result<String, String> {
+    match format {
+        Format::Text => Ok(rose.to_string()),
+        Format::Csv => Ok(rose.to_csv().trim_end().to_string()),
+        Format::Json => write_json(rose),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn write_json(rose: &GildedRose) -> Result<String, String> {
+    Ok(rose.to_json())
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_json(_rose: &GildedRose) -> Result<String, String> {
+    Err("JSON output needs the `serde` feature".to_string())
+}
+
+fn demo() {
     let items = vec![
         Item::new("+5 Dexterity Vest", 10, 20),
         Item::new("Aged Brie", 2, 0),
@@ -28,3 +138,72 @@ fn main() {
         rose.update_quality();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::run;
+
+    fn inventory_file(file_name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("gildedrose-{}-{}", std::process::id(), file_name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_run_csv_inventory() {
+        // GIVEN a CSV inventory on disk
+        let path = inventory_file("run.csv", "name,sell_in,quality\nAged Brie,2,0\nElixir of the Mongoose,5,7\n");
+
+        // WHEN running the tool for three days
+        let output = run(&args(&["--input", path.to_str().unwrap(), "--days", "3"]));
+
+        // THEN the final state should be printed as text
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Ok("Aged Brie, -1, 4\nElixir of the Mongoose, 2, 4".to_string()), output);
+    }
+
+    #[test]
+    fn test_run_defaults_to_one_day() {
+        let path = inventory_file("default.csv", "name,sell_in,quality\nElixir of the Mongoose,5,7\n");
+
+        let output = run(&args(&["--format", "csv", "--input", path.to_str().unwrap()]));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Ok("name,sell_in,quality\nElixir of the Mongoose,4,6".to_string()), output);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_run_json_inventory() {
+        let path = inventory_file("run.json", r#"[{ "name": "Aged Brie", "sell_in": 2, "quality": 0 }]"#);
+
+        let output = run(&args(&["--input", path.to_str().unwrap(), "--format", "json"]));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Ok(r#"[{"name":"Aged Brie","sell_in":1,"quality":1}]"#.to_string()), output);
+    }
+
+    #[test]
+    fn test_run_missing_file() {
+        let output = run(&args(&["--input", "does-not-exist.csv"]));
+
+        assert!(output.unwrap_err().starts_with("could not open `does-not-exist.csv`"));
+    }
+
+    #[test]
+    fn test_run_bad_arguments() {
+        assert!(run(&args(&["--days", "3"])).unwrap_err().starts_with("missing `--input`"));
+        assert!(run(&args(&["--input"])).unwrap_err().starts_with("missing value for `--input`"));
+        assert!(run(&args(&["--input", "x.csv", "--days", "many"])).is_err());
+        assert!(run(&args(&["--input", "x.csv", "--format", "xml"])).is_err());
+        assert!(run(&args(&["--verbose"])).unwrap_err().starts_with("unknown argument `--verbose`"));
+    }
+}